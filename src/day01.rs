@@ -1,62 +1,30 @@
 use crate::prelude::*;
 
-#[aoc(day1, part1)]
-pub fn part1(input: &str) -> u32 {
-    let mut s = 0;
-    for line in input.lines() {
-        let mut vs = vec![];
-        for ch in line.chars() {
-            if let Some(d) = ch.to_digit(10) {
-                vs.push(d);
-            }
-        }
-        s += vs[0] * 10 + vs[vs.len() - 1];
-    }
-    s
+const DIGIT_WORDS: [&str; 10] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+fn calibration_sum(input: &str, scanner: &Scanner<u32>) -> u32 {
+    input
+        .lines()
+        .map(|line| {
+            let (first, last) = scanner.first_and_last(line).unwrap();
+            first.value * 10 + last.value
+        })
+        .sum()
 }
 
-fn str_to_v(s: &str) -> Option<u32> {
-    if let Some(d) = s.chars().next().unwrap().to_digit(10) {
-        Some(d)
-    } else if s.starts_with("one") {
-        Some(1)
-    } else if s.starts_with("two") {
-        Some(2)
-    } else if s.starts_with("three") {
-        Some(3)
-    } else if s.starts_with("four") {
-        Some(4)
-    } else if s.starts_with("five") {
-        Some(5)
-    } else if s.starts_with("six") {
-        Some(6)
-    } else if s.starts_with("seven") {
-        Some(7)
-    } else if s.starts_with("eight") {
-        Some(8)
-    } else if s.starts_with("nine") {
-        Some(9)
-    } else if s.starts_with("zero") {
-        Some(0)
-    } else {
-        None
-    }
+#[aoc(day1, part1)]
+pub fn part1(input: &str) -> u32 {
+    let scanner = Scanner::new((0..10).map(|d| (d.to_string(), d)));
+    calibration_sum(input, &scanner)
 }
 
 #[aoc(day1, part2)]
 pub fn part2(input: &str) -> u32 {
-    let mut s = 0;
-    for line in input.lines() {
-        let mut vs = vec![];
-        for (c, _) in line.char_indices() {
-            let ss = line.split_at(c).1;
-            if let Some(d) = str_to_v(ss) {
-                vs.push(d);
-            }
-        }
-        s += vs[0] * 10 + vs[vs.len() - 1];
-    }
-    s
+    let words = DIGIT_WORDS.iter().zip(0..).map(|(w, d)| (w.to_string(), d));
+    let scanner = Scanner::new((0..10).map(|d| (d.to_string(), d)).chain(words));
+    calibration_sum(input, &scanner)
 }
 
 #[cfg(test)]
@@ -1,102 +1,36 @@
 use crate::prelude::*;
 
+fn is_symbol(ch: char) -> bool {
+    !ch.is_ascii_digit() && ch != '\0' && ch != '.'
+}
+
 #[aoc(day3, part1)]
 pub fn part1(input: &str) -> u32 {
     let grid = parse_char_grid(input);
-    let (i_range, j_range) = get_grid_bounds(&grid);
-
-    let mut contiguous_number_starts = vec![];
-
-    for i in i_range {
-        let mut was_digit = false;
-        for j in j_range.clone() {
-            let ch = grid[&(i, j)];
-            if !was_digit && ch.is_ascii_digit() {
-                contiguous_number_starts.push((i, j));
-            }
-            was_digit = ch.is_ascii_digit();
-        }
-    }
 
-    let mut sums = 0;
-    for (i, j) in contiguous_number_starts {
-        let mut s = 0;
-        let mut is_adjacent = false;
-        for j in j.. {
-            let ch = grid[&(i, j)];
-            if let Some(d) = ch.to_digit(10) {
-                if adjacents((i, j), EIGHT_WAY).any(|pos| {
-                    !grid[&pos].is_ascii_digit() && grid[&pos] != '\0' && grid[&pos] != '.'
-                }) {
-                    is_adjacent = true;
-                }
-                s = s * 10 + d;
-            } else {
-                break;
-            }
-        }
-        if is_adjacent {
-            sums += s;
-        }
-    }
-
-    sums
+    find_spans(&grid, |c| c.is_ascii_digit())
+        .into_iter()
+        .filter(|span| span.border().any(|p| is_symbol(grid[p])))
+        .map(|span| parse1::<u32>(&span.value))
+        .sum()
 }
 
 #[aoc(day3, part2)]
 pub fn part2(input: &str) -> u32 {
     let grid = parse_char_grid(input);
-    let (i_range, j_range) = get_grid_bounds(&grid);
-
-    let mut contiguous_number_starts = vec![];
-
-    for i in i_range {
-        let mut was_digit = false;
-        for j in j_range.clone() {
-            let ch = grid[&(i, j)];
-            if !was_digit && ch.is_ascii_digit() {
-                contiguous_number_starts.push((i, j));
-            }
-            was_digit = ch.is_ascii_digit();
-        }
-    }
-
-    let mut adjacent_to_gears = HashMap::default();
-    let mut values = HashMap::default();
-
-    for (i, j) in contiguous_number_starts {
-        let start = (i, j);
-        let mut s = 0;
-        for j in j.. {
-            let ch = grid[&(i, j)];
-            if let Some(d) = ch.to_digit(10) {
-                for pos in adjacents((i, j), EIGHT_WAY) {
-                    if grid[&pos] == '*' {
-                        adjacent_to_gears
-                            .entry(pos)
-                            .or_insert_with(HashSet::default)
-                            .insert(start);
-                    }
-                }
-                s = s * 10 + d;
-            } else {
-                break;
-            }
-        }
-        values.insert(start, s);
-    }
-
-    let mut sum = 0;
-
-    for starts in adjacent_to_gears.values() {
-        if starts.len() == 2 {
-            let mut s = starts.iter();
-            let gear_ratio = values[&s.next().unwrap()] * values[&s.next().unwrap()];
-            sum += gear_ratio;
-        }
-    }
-
-    sum
+    let index = SpanIndex::new(find_spans(&grid, |c| c.is_ascii_digit()));
+
+    grid.iter()
+        .filter(|(_, ch)| **ch == '*')
+        .map(|(p, _)| index.bordering(*p).collect::<Vec<_>>())
+        .filter(|numbers| numbers.len() == 2)
+        .map(|numbers| {
+            numbers
+                .iter()
+                .map(|span| parse1::<u32>(&span.value))
+                .product::<u32>()
+        })
+        .sum()
 }
 
 #[cfg(test)]
@@ -1,103 +1,43 @@
 use crate::prelude::*;
 
-fn parse_group(input: &str) -> Vec<(i64, i64, i64)> {
-    input
-        .lines()
-        .skip(1)
-        .map(|x| parse3(x.split_whitespace()))
-        .collect()
-}
-
-fn apply_map(m: &[(i64, i64, i64)], v: i64) -> i64 {
-    for (dest_start, source_start, range_len) in m {
-        if v >= *source_start && v < *source_start + *range_len {
-            return *dest_start + v - *source_start;
-        }
-    }
-    v
+fn parse_almanac(input: &str) -> (Vec<i64>, PiecewiseOffsetMap) {
+    let mut sections = Sections::new(input).with_headers();
+    let seeds = sections.next().unwrap().numbers();
+    let seed_to_location = sections
+        .map(|section| {
+            PiecewiseOffsetMap::from_ranges(section.parse_lines(|l| match parse_numbers(l)[..] {
+                [dest_start, source_start, range_len] => {
+                    Some((dest_start, source_start, range_len))
+                }
+                _ => None,
+            }))
+        })
+        .fold(PiecewiseOffsetMap::identity(), |acc, m| acc.compose(&m));
+    (seeds, seed_to_location)
 }
 
 #[aoc(day5, part1)]
 pub fn part1(input: &str) -> i64 {
-    let (seed_str, input) = split1(input, "\n\n");
-    let seed_str = split1(seed_str, ": ").1;
-
-    let mut v = seed_str.split_whitespace().map(parse1).collect::<Vec<_>>();
-    for map in input.split("\n\n") {
-        let map = parse_group(map);
-        v = v.into_iter().map(|s| apply_map(&map, s)).collect();
-    }
-
-    v.into_iter().min().unwrap()
-}
-
-fn apply_map2(
-    m: &[(i64, i64, i64)],
-    intervals: Vec<RangeInclusive<i64>>,
-) -> Vec<RangeInclusive<i64>> {
-    let mut r = vec![];
-
-    for int in intervals {
-        let mut base = vec![int];
-        for (dest_start, source_start, range_len) in m {
-            base = base
-                .into_iter()
-                .flat_map(|int| {
-                    match compute_overlaps(int, *source_start..=source_start + range_len) {
-                        OverlapResult::NonOverlapping { a, .. } => {
-                            vec![a]
-                        }
-                        OverlapResult::Overlapping { overlap, a, .. } => {
-                            let offset = dest_start - source_start;
-                            r.push(overlap.start() + offset..=overlap.end() + offset);
-                            a
-                        }
-                    }
-                })
-                .collect();
-        }
-        r.extend(base);
-    }
-    r
+    let (seeds, map) = parse_almanac(input);
+    seeds.into_iter().map(|s| map.apply(s)).min().unwrap()
 }
 
 #[aoc(day5, part2)]
 pub fn part2(input: &str) -> i64 {
-    let (seed_str, input) = split1(input, "\n\n");
-    let seed_str = split1(seed_str, ": ").1;
-
-    let seeds = seed_str.split_whitespace().map(parse1).collect::<Vec<_>>();
-    let mut v = seeds
-        .chunks(2)
-        .map(|x| x[0]..=x[0] + x[1])
-        .collect::<Vec<_>>();
-    for map in input.split("\n\n") {
-        let map = parse_group(map);
-        v = apply_map2(&map, v);
-    }
-
-    v.into_iter().map(|x| *x.start()).min().unwrap()
+    let (seeds, map) = parse_almanac(input);
+    let ranges = seeds.chunks(2).map(|x| x[0]..=x[0] + x[1] - 1);
+    *map.apply_intervals(ranges)[0].start()
 }
 
 // #[aoc(day5, part1, dumb)]
 pub fn part2dumb(input: &str) -> i64 {
-    let (seed_str, input) = split1(input, "\n\n");
-    let seed_str = split1(seed_str, ": ").1;
-
-    let seeds = seed_str
-        .split_whitespace()
-        .map(parse1)
-        .collect::<Vec<i64>>();
-    let mut v = vec![];
-    for chunk in seeds.chunks(2) {
-        v.extend(chunk[0]..=chunk[0] + chunk[1]);
-    }
-    for map in input.split("\n\n") {
-        let map = parse_group(map);
-        v = v.into_iter().map(|s| apply_map(&map, s)).collect();
-    }
-
-    v.into_iter().min().unwrap()
+    let (seeds, map) = parse_almanac(input);
+    seeds
+        .chunks(2)
+        .flat_map(|chunk| chunk[0]..chunk[0] + chunk[1])
+        .map(|s| map.apply(s))
+        .min()
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -1,22 +1,10 @@
 use crate::prelude::*;
 
-fn solve_simple(time: i64, distance: i64) -> i64 {
-    (0..=time)
-        .filter(|hold| hold * (time - hold) > distance)
-        .count() as i64
-}
-
-// We can directly solve this quadratic equation using the quadratic formula....
-// But the input was actually brute-force-able and there was no need to do so.
-fn solve_quad(time: i64, distance: i64) -> i64 {
-    let a = -1_f64;
-    let b = time as f64;
-    let c = -distance as f64;
-
-    let d = (b * b - 4. * a * c).sqrt();
-    let x1 = (-b + d) / 2.;
-    let x2 = (-b - d) / 2.;
-    (x1 - x2) as i64
+/// Counts the ways to hold the button so that hold * (time - hold) > distance.
+fn ways_to_win(time: i64, distance: i64) -> i64 {
+    quadratic_interval(-1, time.into(), (-distance).into(), true)
+        .map(|range| (range.end() - range.start() + 1) as i64)
+        .unwrap_or(0)
 }
 
 #[aoc(day6, part1)]
@@ -28,7 +16,7 @@ pub fn part1(input: &str) -> i64 {
     times
         .into_iter()
         .zip(distances)
-        .map(|(time, distance)| solve_simple(time, distance))
+        .map(|(time, distance)| ways_to_win(time, distance))
         .product()
 }
 
@@ -37,7 +25,7 @@ pub fn part2(input: &str) -> i64 {
     let (line1, line2) = split1(input, "\n");
     let time: i64 = parse_ints(&line1.replace(' ', ""))[0];
     let distance: i64 = parse_ints(&line2.replace(' ', ""))[0];
-    solve_quad(time, distance)
+    ways_to_win(time, distance)
 }
 
 #[cfg(test)]
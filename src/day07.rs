@@ -1,112 +1,233 @@
 use crate::prelude::*;
 
-fn count_and_type(input: &str) -> i64 {
-    let mut m = HashMap::default();
-    for c in input.chars() {
-        *m.entry(c).or_default() += 1;
+/// How cards compare: `order` lists the cards from weakest to strongest, and
+/// any card in `wildcards` stands in for whichever card makes the best hand.
+#[derive(Debug, Clone)]
+pub struct Ranking {
+    order: Vec<char>,
+    wildcards: HashSet<char>,
+}
+
+impl Ranking {
+    pub fn new(order: &str, wildcards: &str) -> Self {
+        Ranking {
+            order: order.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+        }
+    }
+
+    pub fn standard() -> Self {
+        Ranking::new("23456789TJQKA", "")
+    }
+
+    pub fn jokers() -> Self {
+        Ranking::new("J23456789TQKA", "J")
     }
 
-    let mut v = m.into_iter().collect::<Vec<_>>();
-    v.sort_by_key(|(_, ct)| -ct);
-    type_(&v)
+    pub fn rank(&self, card: char) -> usize {
+        self.order
+            .iter()
+            .position(|c| *c == card)
+            .unwrap_or_else(|| panic!("unknown card {:?}", card))
+    }
+
+    pub fn is_wild(&self, card: char) -> bool {
+        self.wildcards.contains(&card)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
 }
 
-fn type_(hand: &[(char, i64)]) -> i64 {
-    match hand.len() {
-        1 => 0,
-        2 if hand[0].1 == 4 => 1,
-        2 => 2,
-        3 if hand[0].1 == 3 => 3,
-        3 => 4,
-        4 => 5,
-        5 => 6,
-        _ => unreachable!("bad {:?}", hand),
+impl HandType {
+    /// Classifies a hand from its group sizes, largest first.
+    fn from_counts(counts: &[usize]) -> Self {
+        match counts {
+            [5, ..] => HandType::FiveOfAKind,
+            [4, ..] => HandType::FourOfAKind,
+            [3, 2, ..] => HandType::FullHouse,
+            [3, ..] => HandType::ThreeOfAKind,
+            [2, 2, ..] => HandType::TwoPair,
+            [2, ..] => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
     }
 }
 
-fn char_(v: char) -> usize {
-    "AKQJT98765432".find(v).unwrap()
+/// A Camel Cards hand: ordered by type, then card by card in dealt order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hand {
+    pub cards: String,
+    pub hand_type: HandType,
+    ranks: Vec<usize>,
 }
 
-#[aoc(day7, part1)]
-pub fn part1(input: &str) -> i64 {
-    let mut rows = input
-        .lines()
-        .map(|line| split1(line, " "))
-        .collect::<Vec<(&str, &str)>>();
-
-    rows.sort_by(|a, b| {
-        let v = count_and_type(a.0).cmp(&count_and_type(b.0));
-        v.then_with(|| {
-            a.0.chars()
-                .zip(b.0.chars())
-                .map(|(aa, bb)| char_(aa).cmp(&char_(bb)))
-                .find(|c| *c != Ordering::Equal)
-                .unwrap_or(Ordering::Equal)
-        })
-        .reverse()
-    });
+impl Hand {
+    pub fn new(cards: &str, ranking: &Ranking) -> Self {
+        let mut counts: HashMap<char, usize> = HashMap::default();
+        let mut wild = 0;
+        for c in cards.chars() {
+            if ranking.is_wild(c) {
+                wild += 1;
+            } else {
+                *counts.entry(c).or_default() += 1;
+            }
+        }
 
-    rows.into_iter()
-        .enumerate()
-        .map(|(idx, (_, bid))| parse1::<i64>(bid) * ((idx + 1) as i64))
-        .sum::<i64>()
+        let mut counts = counts.into_values().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.cmp(a));
+        match counts.first_mut() {
+            Some(largest) => *largest += wild,
+            None => counts.push(wild),
+        }
+
+        Hand {
+            cards: cards.to_string(),
+            hand_type: HandType::from_counts(&counts),
+            ranks: cards.chars().map(|c| ranking.rank(c)).collect(),
+        }
+    }
 }
 
-fn count_and_max_type_(input: &str) -> i64 {
-    assert_eq!(input.len(), 5);
-    let mut m = HashMap::default();
-    for c in input.chars() {
-        *m.entry(c).or_default() += 1;
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hand_type
+            .cmp(&other.hand_type)
+            .then_with(|| self.ranks.cmp(&other.ranks))
     }
+}
 
-    let mut v = m.iter().map(|(a, b)| (*a, *b)).collect::<Vec<_>>();
-    v.sort_by_key(|(_, ct)| -ct);
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-    if let Some(x) = m.remove(&'J') {
-        if let Some(non_j) = v.iter().find(|x| x.0 != 'J') {
-            *m.entry(non_j.0).or_default() += x;
-        } else {
-            m.insert('J', x);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PokerCategory {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+/// A five card poker hand such as `"AS KD 3C 3H 9S"`, ordered by category and
+/// then by kickers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PokerHand {
+    pub category: PokerCategory,
+    /// Ranks in the order they break ties: larger groups first, then higher
+    /// cards.
+    pub kickers: Vec<usize>,
+}
+
+impl PokerHand {
+    pub fn new(cards: &[(char, char)], ranking: &Ranking) -> Self {
+        assert_eq!(cards.len(), 5, "poker hands have five cards");
+
+        let mut counts: HashMap<usize, usize> = HashMap::default();
+        for (rank, _) in cards {
+            *counts.entry(ranking.rank(*rank)).or_default() += 1;
         }
+        let mut groups = counts.into_iter().collect::<Vec<_>>();
+        groups.sort_by(|(r1, c1), (r2, c2)| c2.cmp(c1).then(r2.cmp(r1)));
+
+        let mut kickers = groups.iter().map(|(r, _)| *r).collect::<Vec<_>>();
+        let sizes = groups.iter().map(|(_, c)| *c).collect::<Vec<_>>();
+
+        let flush = cards.iter().all(|(_, suit)| *suit == cards[0].1);
+        let straight = if sizes.len() < 5 {
+            false
+        } else if kickers[0] - kickers[4] == 4 {
+            true
+        } else if kickers == [ranking.order.len() - 1, 3, 2, 1, 0] {
+            // The ace plays low in A-2-3-4-5.
+            kickers.rotate_left(1);
+            true
+        } else {
+            false
+        };
+
+        let category = match HandType::from_counts(&sizes) {
+            _ if straight && flush => PokerCategory::StraightFlush,
+            HandType::FourOfAKind => PokerCategory::FourOfAKind,
+            HandType::FullHouse => PokerCategory::FullHouse,
+            _ if flush => PokerCategory::Flush,
+            _ if straight => PokerCategory::Straight,
+            HandType::ThreeOfAKind => PokerCategory::ThreeOfAKind,
+            HandType::TwoPair => PokerCategory::TwoPair,
+            HandType::OnePair => PokerCategory::OnePair,
+            _ => PokerCategory::HighCard,
+        };
+
+        PokerHand { category, kickers }
     }
 
-    let mut v = m.into_iter().collect::<Vec<_>>();
-    v.sort_by_key(|(_, ct)| -ct);
-    assert!(!v.is_empty());
+    pub fn parse(input: &str) -> Self {
+        let cards = input
+            .split_whitespace()
+            .map(|card| {
+                let mut chars = card.chars();
+                (chars.next().unwrap(), chars.next().unwrap())
+            })
+            .collect::<Vec<_>>();
+        PokerHand::new(&cards, &Ranking::standard())
+    }
+}
 
-    type_(&v)
+impl Ord for PokerHand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.category
+            .cmp(&other.category)
+            .then_with(|| self.kickers.cmp(&other.kickers))
+    }
 }
 
-fn char_2(v: char) -> usize {
-    "AKQT98765432J".find(v).unwrap()
+impl PartialOrd for PokerHand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
-#[aoc(day7, part2)]
-pub fn part2(input: &str) -> i64 {
+fn total_winnings(input: &str, ranking: &Ranking) -> i64 {
     let mut rows = input
         .lines()
-        .map(|line| split1(line, " "))
-        .collect::<Vec<(&str, &str)>>();
-
-    rows.sort_by(|a, b| {
-        let v = count_and_max_type_(a.0).cmp(&count_and_max_type_(b.0));
-        v.then_with(|| {
-            a.0.chars()
-                .zip(b.0.chars())
-                .map(|(aa, bb)| char_2(aa).cmp(&char_2(bb)))
-                .find(|c| *c != Ordering::Equal)
-                .unwrap_or(Ordering::Equal)
+        .map(|line| {
+            let (cards, bid) = split1(line, " ");
+            (Hand::new(cards, ranking), parse1::<i64>(bid))
         })
-        .reverse()
-    });
+        .collect::<Vec<_>>();
+    rows.sort();
 
     rows.into_iter()
         .enumerate()
-        .map(|(idx, (_, bid))| parse1::<i64>(bid) * ((idx + 1) as i64))
+        .map(|(idx, (_, bid))| bid * ((idx + 1) as i64))
         .sum::<i64>()
 }
 
+#[aoc(day7, part1)]
+pub fn part1(input: &str) -> i64 {
+    total_winnings(input, &Ranking::standard())
+}
+
+#[aoc(day7, part2)]
+pub fn part2(input: &str) -> i64 {
+    total_winnings(input, &Ranking::jokers())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +247,30 @@ QQQJA 483"#;
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 5905);
     }
+
+    #[test]
+    fn hand_types() {
+        let jokers = Ranking::jokers();
+        assert_eq!(Hand::new("JJJJJ", &jokers).hand_type, HandType::FiveOfAKind);
+        assert_eq!(Hand::new("KTJJT", &jokers).hand_type, HandType::FourOfAKind);
+        assert_eq!(
+            Hand::new("KTJJT", &Ranking::standard()).hand_type,
+            HandType::TwoPair
+        );
+        assert!(Hand::new("JKKK2", &jokers) < Hand::new("QQQQ2", &jokers));
+    }
+
+    #[test]
+    fn poker_hands() {
+        let hand = PokerHand::parse;
+        assert_eq!(
+            hand("TS JS QS KS AS").category,
+            PokerCategory::StraightFlush
+        );
+        assert_eq!(hand("AD 2C 3H 4S 5S").category, PokerCategory::Straight);
+        assert_eq!(hand("2H 7H 9H JH KH").category, PokerCategory::Flush);
+        assert!(hand("AD 2C 3H 4S 5S") < hand("2D 3C 4H 5S 6S"));
+        assert!(hand("3D 3C 9H 9S AS") > hand("3D 3C 9H 9S KS"));
+        assert!(hand("2D 2C 2H 9S 9H") > hand("AD KD QD JD 9D"));
+    }
 }
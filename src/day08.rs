@@ -1,17 +1,24 @@
 use crate::prelude::*;
 
-#[aoc(day8, part1)]
-pub fn part1(input: &str) -> i64 {
-    let (instructions, lookups) = split1(input, "\n\n");
+fn parse_network(input: &str) -> (&str, DefaultHashMap<&str, (&str, &str)>) {
+    let mut sections = Sections::new(input);
+    let instructions = sections.next().unwrap().lines().next().unwrap();
 
     let mut map = DefaultHashMap::default();
-
-    for line in lookups.lines() {
-        let (from, leftright) = split1(line, " = (");
-        let (left, right) = split1(leftright.trim_end_matches(')'), ", ");
-        map[from] = (left, right);
+    for (from, leftright) in sections.next().unwrap().parse_lines(|line| {
+        let (from, leftright) = line.split_once(" = (")?;
+        Some((from, leftright.trim_end_matches(')').split_once(", ")?))
+    }) {
+        map[from] = leftright;
     }
 
+    (instructions, map)
+}
+
+#[aoc(day8, part1)]
+pub fn part1(input: &str) -> i64 {
+    let (instructions, map) = parse_network(input);
+
     let mut pos = "AAA";
     for (idx, c) in instructions.chars().cycle().enumerate() {
         pos = match c {
@@ -28,15 +35,7 @@ pub fn part1(input: &str) -> i64 {
 
 #[aoc(day8, part2)]
 pub fn part2(input: &str) -> i64 {
-    let (instructions, lookups) = split1(input, "\n\n");
-
-    let mut map = DefaultHashMap::default();
-
-    for line in lookups.lines() {
-        let (from, leftright) = split1(line, " = (");
-        let (left, right) = split1(leftright.trim_end_matches(')'), ", ");
-        map[from] = (left, right);
-    }
+    let (instructions, map) = parse_network(input);
 
     map.keys()
         .filter(|k| k.ends_with('A'))
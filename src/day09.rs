@@ -1,27 +1,22 @@
 use crate::prelude::*;
 
+fn sum_extrapolated(input: &str, f: impl Fn(&DifferenceTable) -> BigInt) -> i64 {
+    input
+        .lines()
+        .map(|line| f(&DifferenceTable::new(&parse_ints(line))))
+        .sum::<BigInt>()
+        .try_into()
+        .unwrap()
+}
+
 #[aoc(day9, part1)]
 pub fn part1(input: &str) -> i64 {
-    let mut s = 0;
-    for line in input.lines() {
-        let y = parse_ints(line);
-        let p = polynomial_regression(&(0i64..y.len() as i64).collect::<Vec<_>>(), &y, y.len() - 1);
-        s += p.eval(y.len() as i64);
-    }
-
-    s
+    sum_extrapolated(input, |t| t.forward(1))
 }
 
 #[aoc(day9, part2)]
 pub fn part2(input: &str) -> i64 {
-    let mut s = 0;
-    for line in input.lines() {
-        let y = parse_ints(line);
-        let p = polynomial_regression(&(0i64..y.len() as i64).collect::<Vec<_>>(), &y, y.len() - 1);
-        s += p.eval(-1);
-    }
-
-    s
+    sum_extrapolated(input, |t| t.backward(1))
 }
 
 #[cfg(test)]
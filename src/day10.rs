@@ -1,87 +1,171 @@
-use petgraph::data::Build;
-
 use crate::prelude::*;
 
-fn get_loop(input: &str) -> Vec<(i64, i64)> {
-    let grid = parse_char_grid(input);
+const PIPES: &str = "|-LJ7F";
+
+fn pipe_exits(ch: char) -> Option<[Point; 2]> {
+    match ch {
+        '|' => Some([UP, DOWN]),
+        '-' => Some([LEFT, RIGHT]),
+        'L' => Some([UP, RIGHT]),
+        'J' => Some([UP, LEFT]),
+        '7' => Some([LEFT, DOWN]),
+        'F' => Some([RIGHT, DOWN]),
+        _ => None,
+    }
+}
 
-    let (i_bounds, j_bounds) = get_grid_bounds(&grid);
-    let mut s_pos = (-1, -1);
-    let mut g = DiGraphMap::default();
+/// Follows the pipes from `start` until getting back to it, or returns `None`
+/// if they lead anywhere else.
+fn walk_loop(grid: &IGrid2D, start: Point) -> Option<Vec<Point>> {
+    let mut path = vec![start];
+    let mut dir = pipe_exits(grid[start])?[0];
+    loop {
+        let pos = point_add(*path.last().unwrap(), dir);
+        if pos == start {
+            return Some(path);
+        }
+        if path.len() > grid.len() {
+            return None;
+        }
+        path.push(pos);
 
-    for i in i_bounds.clone() {
-        for j in j_bounds.clone() {
-            let p = (i, j);
-            if grid[&p] == 'S' {
-                s_pos = (i, j);
-            }
+        let exits = pipe_exits(grid[pos])?;
+        if !exits.contains(&point_neg(dir)) {
+            return None;
+        }
+        dir = exits.into_iter().find(|d| *d != point_neg(dir))?;
+    }
+}
 
-            if "|-LJ7F".find(grid[&(i, j)]).is_some() {
-                let exits = match grid[&p] {
-                    '|' => [UP, DOWN],
-                    '-' => [LEFT, RIGHT],
-                    'L' => [UP, RIGHT],
-                    'J' => [UP, LEFT],
-                    '7' => [LEFT, DOWN],
-                    'F' => [RIGHT, DOWN],
-                    _ => unreachable!(),
-                };
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Loop,
+    Inside,
+    Outside,
+}
 
-                for d in exits {
-                    let dst = point_add(p, d);
-                    g.update_edge(p, dst, ());
-                }
-            }
-        }
+#[derive(Debug, Clone)]
+pub struct PipeMaze {
+    /// The input grid, with `S` replaced by its actual pipe.
+    pub grid: IGrid2D,
+    pub start: Point,
+    /// The loop starting at `S`, in traversal order.
+    pub path: Vec<Point>,
+}
+
+impl PipeMaze {
+    pub fn parse(input: &str) -> Self {
+        let mut grid = parse_char_grid(input);
+        let (start, _) = grid.iter().find(|(_, c)| **c == 'S').unwrap();
+        let start = *start;
+
+        // S is whichever pipe connects to two neighbors which connect back to
+        // it. Pipes off the loop can also point at S, so check that the loop
+        // actually closes.
+        let connected = FOUR_WAY
+            .into_iter()
+            .filter(|d| {
+                pipe_exits(grid[point_add(start, *d)])
+                    .map(|exits| exits.contains(&point_neg(*d)))
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        let (shape, path) = PIPES
+            .chars()
+            .filter(|c| {
+                pipe_exits(*c)
+                    .unwrap()
+                    .iter()
+                    .all(|e| connected.contains(e))
+            })
+            .find_map(|c| {
+                grid.insert(start, c);
+                Some((c, walk_loop(&grid, start)?))
+            })
+            .expect("S isn't part of a loop");
+        grid.insert(start, shape);
+
+        PipeMaze { grid, start, path }
     }
 
-    let into_s_orig = g.neighbors_directed(s_pos, Incoming).collect::<Vec<_>>();
-    for n in into_s_orig {
-        g.update_edge(s_pos, n, ());
+    pub fn start_shape(&self) -> char {
+        self.grid[self.start]
     }
 
-    let mut stk = vec![(s_pos, None)];
-    let mut backtrack = HashMap::default();
-    while let Some((curr, prev)) = stk.pop() {
-        if curr == s_pos && prev.is_some() {
-            break;
-        }
-        for next in g.neighbors(curr) {
-            if Some(next) == prev {
-                continue;
+    /// Classifies every tile by casting a ray to the left of it and counting
+    /// how many times it crosses the loop.
+    pub fn classify(&self) -> HashMap<Point, Tile> {
+        let on_loop = self.path.iter().copied().collect::<HashSet<_>>();
+        let (i_bounds, j_bounds) = get_grid_bounds(&self.grid);
+        let mut tiles = HashMap::default();
+
+        for i in i_bounds {
+            let mut inside = false;
+            for j in j_bounds.clone() {
+                let p = (i, j);
+                let tile = if on_loop.contains(&p) {
+                    // Only count pipes which go up, so that running along a
+                    // horizontal section is handled correctly.
+                    if "|LJ".contains(self.grid[p]) {
+                        inside = !inside;
+                    }
+                    Tile::Loop
+                } else if inside {
+                    Tile::Inside
+                } else {
+                    Tile::Outside
+                };
+                tiles.insert(p, tile);
             }
-            stk.push((next, Some(curr)));
-            backtrack.insert(next, curr);
         }
+        tiles
     }
 
-    let mut path = vec![s_pos];
-    loop {
-        let x = backtrack[&path.last().unwrap()];
-        if x == s_pos {
-            break;
-        }
-        path.push(x);
+    /// Number of tiles enclosed by the loop.
+    pub fn enclosed_area(&self) -> i64 {
+        let total_area = compute_lattice_polygon_area(self.path.clone());
+        // We want the interior area, not the total area of the polygon. We can
+        // apply Pick's theorem to subtract out the boundary.
+        total_area.abs() - (self.path.len() as i64) / 2 + 1
     }
 
-    path
+    /// Draws the loop with box-drawing characters, inside tiles as `I` and
+    /// outside tiles as spaces.
+    pub fn render(&self) -> String {
+        let tiles = self.classify();
+        let (i_bounds, j_bounds) = get_grid_bounds(&self.grid);
+        let mut s = String::new();
+
+        for i in i_bounds {
+            for j in j_bounds.clone() {
+                s.push(match tiles[&(i, j)] {
+                    Tile::Loop => match self.grid[(i, j)] {
+                        '|' => '│',
+                        '-' => '─',
+                        'L' => '└',
+                        'J' => '┘',
+                        '7' => '┐',
+                        'F' => '┌',
+                        _ => unreachable!(),
+                    },
+                    Tile::Inside => 'I',
+                    Tile::Outside => ' ',
+                });
+            }
+            s.push('\n');
+        }
+        s
+    }
 }
 
 #[aoc(day10, part1)]
 pub fn part1(input: &str) -> i64 {
-    let inloop = get_loop(input);
-    (inloop.len() / 2) as i64
+    (PipeMaze::parse(input).path.len() / 2) as i64
 }
 
 #[aoc(day10, part2)]
 pub fn part2(input: &str) -> i64 {
-    let path = get_loop(input);
-    let path_len = path.len() as i64;
-
-    let total_area = compute_lattice_polygon_area(path);
-    // We want the interior area, not the total area of the polygon. We can
-    // apply Pick's theorem to subtract out the boundary.
-    total_area.abs() - (path_len) / 2 + 1
+    PipeMaze::parse(input).enclosed_area()
 }
 
 #[cfg(test)]
@@ -112,4 +196,32 @@ LJ..."#;
     fn part2_example() {
         assert_eq!(part2(EXAMPLE2), 4);
     }
+
+    #[test]
+    fn start_with_extra_neighbor() {
+        // The `-` left of S points into it but isn't on the loop.
+        let input = ".....\n-S-7.\n.|.|.\n.L-J.";
+        let maze = PipeMaze::parse(input);
+        assert_eq!(maze.start_shape(), 'F');
+        assert_eq!(part1(input), 4);
+        assert_eq!(part2(input), 1);
+    }
+
+    #[test]
+    fn pipe_maze() {
+        let maze = PipeMaze::parse(EXAMPLE);
+        assert_eq!(maze.start_shape(), 'F');
+        assert_eq!(maze.path[..3], [(2, 0), (2, 1), (1, 1)]);
+
+        let maze = PipeMaze::parse(EXAMPLE2);
+        let inside = maze
+            .classify()
+            .values()
+            .filter(|t| **t == Tile::Inside)
+            .count();
+        assert_eq!(inside as i64, maze.enclosed_area());
+
+        let maze = PipeMaze::parse("S-7\n|.|\nL-J");
+        assert_eq!(maze.render(), "┌─┐\n│I│\n└─┘\n");
+    }
 }
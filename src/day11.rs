@@ -1,52 +1,23 @@
 use crate::prelude::*;
 
 fn solve(input: &str, mul: i64) -> i64 {
-    let orig_grid = parse_char_grid(input);
-    let (i_bounds, j_bounds) = get_grid_bounds(&orig_grid);
-
-    let mut galaxy_rows = HashSet::default();
-    let mut galaxy_cols = HashSet::default();
-
-    for i in i_bounds.clone() {
-        for j in j_bounds.clone() {
-            if orig_grid[&(i, j)] == '#' {
-                galaxy_rows.insert(i);
-                galaxy_cols.insert(j);
-            }
-        }
-    }
-
-    let mut galaxies = vec![];
-    let mut y = 0;
-    for i in i_bounds {
-        if !galaxy_rows.contains(&i) {
-            y += mul - 1;
-        }
-        let mut x = 0;
-        for j in j_bounds.clone() {
-            if !galaxy_cols.contains(&j) {
-                x += mul - 1;
-            }
-            if orig_grid[&(i, j)] == '#' {
-                galaxies.push((y, x));
-            }
-            x += 1;
-        }
-        y += 1;
-    }
-
-    let mut s = 0;
-
-    for (idx, g1) in galaxies.iter().enumerate() {
-        for g2 in galaxies.iter().skip(idx) {
-            if g1 != g2 {
-                let d = g1.0.max(g2.0) - g1.0.min(g2.0) + g1.1.max(g2.1) - g1.1.min(g2.1);
-                s += d;
-            }
-        }
-    }
-
-    s
+    let grid = parse_char_grid(input);
+    let (i_bounds, j_bounds) = get_grid_bounds(&grid);
+
+    let galaxies = grid
+        .iter()
+        .filter(|(_, c)| **c == '#')
+        .map(|(p, _)| *p)
+        .collect::<Vec<_>>();
+
+    let rows = ExpansionMap::new(galaxies.iter().map(|p| p.0), i_bounds, mul);
+    let cols = ExpansionMap::new(galaxies.iter().map(|p| p.1), j_bounds, mul);
+    let expanded = galaxies
+        .iter()
+        .map(|(i, j)| (rows.get(*i), cols.get(*j)))
+        .collect::<Vec<_>>();
+
+    manhattan_distance_sum(&expanded)
 }
 
 #[aoc(day11, part1)]
@@ -1,40 +1,19 @@
 use crate::prelude::*;
 
-fn solve(input: &str, smudges: i64) -> i64 {
+fn solve(input: &str, smudges: usize) -> i64 {
     let mut s = 0;
-    for pattern in input.split("\n\n") {
-        let grid = parse_char_grid(pattern);
-        // find a perfect reflection across either a horizontal line between two rows or vertical between two columns
-
-        let (i_bounds, j_bounds) = get_grid_bounds(&grid);
-
-        for i in 1..i_bounds.end {
-            let n = i.min(i_bounds.end - i - 1);
-            let mut mismatches = 0;
-            for r in 0..=n {
-                for (a, b) in get_grid_row(&grid, i - r - 1).zip(get_grid_row(&grid, i + r)) {
-                    if a != b && a != '\0' && b != '\0' {
-                        mismatches += 1;
-                    }
-                }
-            }
-            if mismatches == smudges {
-                s += 100 * i;
-            }
-        }
-
-        for j in 1..j_bounds.end {
-            let n = j.min(j_bounds.end - j - 1);
-            let mut mismatches = 0;
-            for r in 0..=n {
-                for (a, b) in get_grid_col(&grid, j - r - 1).zip(get_grid_col(&grid, j + r)) {
-                    if a != b && a != '\0' && b != '\0' {
-                        mismatches += 1;
-                    }
-                }
+    for pattern in Sections::new(input) {
+        let grid = parse_char_grid(&pattern.text());
+        // find a reflection across either a horizontal line between two rows
+        // or vertical between two columns with exactly `smudges` mismatches
+        for axis in find_reflection_axes(&grid, smudges) {
+            if axis.mismatches.len() != smudges {
+                continue;
             }
-            if mismatches == smudges {
-                s += j;
+            match (axis.orientation, axis.split_index()) {
+                (AxisOrientation::Horizontal, Some(i)) => s += 100 * i,
+                (AxisOrientation::Vertical, Some(j)) => s += j,
+                (_, None) => (),
             }
         }
     }
@@ -1,60 +1,106 @@
 use crate::prelude::*;
 
-fn fall_north(grid: &mut IGrid2D) {
-    let mut pts = grid.drain().collect::<Vec<_>>();
-    pts.sort();
+/// Cells of the grid as lines parallel to `dir`, each ordered starting from
+/// the edge that `dir` points towards.
+fn lines_towards(dir: Point, (i_bounds, j_bounds): &(Range<i64>, Range<i64>)) -> Vec<Vec<Point>> {
+    let ordered = |r: &Range<i64>, reverse: bool| {
+        let mut v = r.clone().collect::<Vec<_>>();
+        if reverse {
+            v.reverse();
+        }
+        v
+    };
+    match dir {
+        UP | DOWN => j_bounds
+            .clone()
+            .map(|j| {
+                ordered(i_bounds, dir == DOWN)
+                    .into_iter()
+                    .map(|i| (i, j))
+                    .collect()
+            })
+            .collect(),
+        LEFT | RIGHT => i_bounds
+            .clone()
+            .map(|i| {
+                ordered(j_bounds, dir == RIGHT)
+                    .into_iter()
+                    .map(|j| (i, j))
+                    .collect()
+            })
+            .collect(),
+        _ => unreachable!("can only tilt in one of FOUR_WAY"),
+    }
+}
 
-    for (mut p, c) in pts {
-        if c == '#' {
-            grid.insert(p, '#');
-        } else if c == 'O' {
-            while grid[&(p.0 - 1, p.1)] == '.' || grid[&(p.0 - 1, p.1)] == '\0' && p.0 > 0 {
-                p.0 -= 1;
+fn tilt_in_bounds(grid: &mut IGrid2D, dir: Point, bounds: &(Range<i64>, Range<i64>)) {
+    for line in lines_towards(dir, bounds) {
+        // Next place that a rolling rock would stop
+        let mut free = 0;
+        for (k, p) in line.iter().enumerate() {
+            match grid[p] {
+                '#' => free = k + 1,
+                'O' => {
+                    if free != k {
+                        grid[*p] = '.';
+                        grid[line[free]] = 'O';
+                    }
+                    free += 1;
+                }
+                _ => (),
             }
-            grid.insert(p, 'O');
         }
     }
 }
 
-#[aoc(day14, part1)]
-pub fn part1(input: &str) -> i64 {
-    let mut g = parse_char_grid(input);
-    fall_north(&mut g);
-    score(&g)
+/// Rolls every `O` as far as it will go in `dir`, stopping at `#`, other
+/// rocks and the edge of the grid.
+pub fn tilt(grid: &mut IGrid2D, dir: Point) {
+    let bounds = get_grid_bounds(grid);
+    tilt_in_bounds(grid, dir, &bounds);
 }
 
-fn score(g: &IGrid2D) -> i64 {
-    let mut sum = 0;
-    // score
-    let (i_bounds, _) = get_grid_bounds(g);
-
-    for (pt, ch) in g.iter() {
-        if *ch == 'O' {
-            sum += i_bounds.end - pt.0;
-        }
+/// Tilts north, then west, then south, then east.
+pub fn spin_cycle(grid: &mut IGrid2D) {
+    let bounds = get_grid_bounds(grid);
+    for dir in [UP, LEFT, DOWN, RIGHT] {
+        tilt_in_bounds(grid, dir, &bounds);
     }
-    sum
+}
+
+/// Each rock contributes the number of rows (or columns) from it to the edge
+/// opposite `dir`, including its own.
+pub fn load(grid: &IGrid2D, dir: Point) -> i64 {
+    let (i_bounds, j_bounds) = get_grid_bounds(grid);
+
+    grid.iter()
+        .filter(|(_, ch)| **ch == 'O')
+        .map(|((i, j), _)| match dir {
+            UP => i_bounds.end - i,
+            DOWN => i - i_bounds.start + 1,
+            LEFT => j_bounds.end - j,
+            RIGHT => j - j_bounds.start + 1,
+            _ => unreachable!("can only measure load in one of FOUR_WAY"),
+        })
+        .sum()
+}
+
+#[aoc(day14, part1)]
+pub fn part1(input: &str) -> i64 {
+    let mut g = parse_char_grid(input);
+    tilt(&mut g, UP);
+    load(&g, UP)
 }
 
 #[aoc(day14, part2)]
 pub fn part2(input: &str) -> i64 {
-    let mut g = parse_char_grid(input);
-    g.retain(|_, v| *v != '.');
+    let g = parse_char_grid(input);
 
-    let res = find_cycle_equals(
-        g.clone(),
-        |g2| {
-            for _ in 0..4 {
-                fall_north(g2);
-                rotate_grid_inplace_cw(g2);
-            }
-        },
-        |g2| {
-            let mut h = FnvHasher::default();
-            g2.hash(&mut h);
-            (h.finish(), score(g2))
-        },
-    );
+    let res = find_cycle_equals(g, spin_cycle, |g2| {
+        let mut h = FnvHasher::default();
+        g2.hash(&mut h);
+        (h.finish(), load(g2, UP))
+    });
     res[1000000000 + 1].1
 }
 
@@ -82,4 +128,20 @@ O.#..O.#.#
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 64);
     }
+
+    #[test]
+    fn tilt_directions() {
+        let mut g = parse_char_grid(".O.#O.\nO....O");
+        tilt(&mut g, LEFT);
+        assert_eq!(get_grid_row(&g, 0).collect::<String>(), "O..#O.");
+        assert_eq!(load(&g, LEFT), 6 + 2 + 6 + 5);
+        tilt(&mut g, RIGHT);
+        assert_eq!(get_grid_row(&g, 0).collect::<String>(), "..O#.O");
+        assert_eq!(get_grid_row(&g, 1).collect::<String>(), "....OO");
+        tilt(&mut g, UP);
+        assert_eq!(get_grid_row(&g, 1).collect::<String>(), ".....O");
+        tilt(&mut g, DOWN);
+        assert_eq!(get_grid_row(&g, 0).collect::<String>(), "...#.O");
+        assert_eq!(load(&g, DOWN), 1 + 2 * 3);
+    }
 }
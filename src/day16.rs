@@ -1,73 +1,13 @@
 use crate::prelude::*;
 
-fn solve(g: &IGrid2D, initial_beams: Vec<((i64, i64), (i64, i64))>) -> i64 {
-    let mut max_energized = -1;
-    let (i_bounds, j_bounds) = get_grid_bounds(g);
-
-    for beam in initial_beams {
-        let mut beams = vec![beam];
-        let mut visited = HashSet::default();
-
-        while let Some((pos, mut dir)) = beams.pop() {
-            visited.insert((pos, dir));
-
-            let mut to_add = vec![];
-            if "./\\".find(g[pos]).is_some() {
-                if g[pos] == '.' {
-                } else if g[pos] == '/' {
-                    if dir == RIGHT {
-                        dir = UP;
-                    } else if dir == LEFT {
-                        dir = DOWN;
-                    } else if dir == UP {
-                        dir = RIGHT;
-                    } else if dir == DOWN {
-                        dir = LEFT;
-                    }
-                } else if g[pos] == '\\' {
-                    if dir == RIGHT {
-                        dir = DOWN;
-                    } else if dir == LEFT {
-                        dir = UP;
-                    } else if dir == UP {
-                        dir = LEFT;
-                    } else if dir == DOWN {
-                        dir = RIGHT;
-                    }
-                }
-
-                to_add.push((point_add(pos, dir), dir));
-            } else if "-|".find(g[pos]).is_some() {
-                if (g[pos] == '-' && (dir == RIGHT || dir == LEFT))
-                    || (g[pos] == '|' && (dir == UP || dir == DOWN))
-                {
-                    to_add.push((point_add(pos, dir), dir));
-                } else if g[pos] == '-' {
-                    to_add.push((point_add(pos, LEFT), LEFT));
-                    to_add.push((point_add(pos, RIGHT), RIGHT));
-                } else if g[pos] == '|' {
-                    to_add.push((point_add(pos, UP), UP));
-                    to_add.push((point_add(pos, DOWN), DOWN));
-                }
-            }
-            to_add.retain(|d @ ((i, j), _)| {
-                !visited.contains(d)
-                    && *i >= i_bounds.start
-                    && *i < i_bounds.end
-                    && *j >= j_bounds.start
-                    && *j < j_bounds.end
-            });
-            beams.extend(to_add);
-        }
-        max_energized = max_energized.max(
-            visited
-                .into_iter()
-                .map(|x| x.0)
-                .collect::<HashSet<_>>()
-                .len() as i64,
-        )
-    }
-    max_energized
+fn solve(g: &IGrid2D, initial_beams: Vec<Beam>) -> i64 {
+    let mut tracer = BeamTracer::new(g, OpticsTable::mirrors_and_splitters());
+
+    initial_beams
+        .into_iter()
+        .map(|beam| tracer.trace(beam).energized() as i64)
+        .max()
+        .unwrap_or(-1)
 }
 
 #[aoc(day16, part1)]
@@ -7,66 +7,24 @@ fn solve(input: &str, min_dist: i64, max_dist: i64) -> i64 {
     let (i_bounds, j_bounds) = get_grid_bounds(&grid);
     let dest_pos = (i_bounds.end - 1, j_bounds.end - 1);
 
-    #[derive(Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
-    struct S {
-        cost: i64,
-        pos: (i64, i64),
-        from_dir: (i64, i64),
-    }
-
-    let mut q = BinaryHeap::new();
-    q.push(Reverse(S {
-        cost: 0,
-        pos: start_pos,
-        from_dir: (0, 0),
-    }));
-    let mut dist = DefaultHashMap::new(std::i64::MAX);
-    dist[(start_pos, (0, 0))] = 0;
-
-    while let Some(Reverse(S {
-        cost,
-        pos,
-        from_dir,
-    })) = q.pop()
-    {
-        if pos == dest_pos {
-            return cost;
-        }
-        // Compute forward edges
-        for dir in FOUR_WAY {
-            if dir == from_dir || dir == point_neg(from_dir) {
-                continue;
-            }
+    // Must turn after at most `max_dist`, and can't turn around.
+    let rules = MovementRules {
+        min_straight: min_dist,
+        max_straight: max_dist,
+        allow_turns: true,
+        allow_reversal: false,
+    };
 
-            let mut cost_sum = 0;
-            for n in 1..=max_dist {
-                let p = point_add(pos, point_mul(dir, n));
-                let c = grid[p];
-
-                if let Some(c) = c.to_digit(10) {
-                    cost_sum += c as i64;
-                    if n < min_dist {
-                        continue;
-                    }
-                    let next = S {
-                        cost: cost + cost_sum,
-                        pos: p,
-                        from_dir: dir,
-                    };
-                    if next.cost < dist[(next.pos, next.from_dir)] {
-                        q.push(Reverse(next));
-                        dist[(next.pos, next.from_dir)] = next.cost;
-                    }
-                }
-            }
-        }
-    }
-    unreachable!()
+    constrained_shortest_path(&grid, start_pos, dest_pos, &rules, |c| {
+        c.to_digit(10).map(|d| d as i64)
+    })
+    .unwrap()
+    .cost
 }
 
 #[aoc(day17, part1)]
 pub fn part1(input: &str) -> i64 {
-    solve(input, 0, 3)
+    solve(input, 1, 3)
 }
 
 #[aoc(day17, part2)]
@@ -97,8 +55,15 @@ mod tests {
         assert_eq!(part1(EXAMPLE), 102);
     }
 
+    const EXAMPLE2: &str = r#"111111111111
+999999999991
+999999999991
+999999999991
+999999999991"#;
+
     #[test]
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 94);
+        assert_eq!(part2(EXAMPLE2), 71);
     }
 }
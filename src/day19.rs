@@ -1,133 +1,404 @@
 use crate::prelude::*;
 
-fn parse_rules(rules_str: &str) -> HashMap<String, Vec<(String, char, i64, String)>> {
-    let mut rules = HashMap::default();
-    for r in rules_str.lines() {
-        let mut rr = vec![];
-        let (name, r) = split1(r, "{");
-        for s in r.trim_end_matches(|c| c == '}').split(',') {
-            if s.contains('<') {
-                let (expr, res) = split1(s, ":");
-                let (var, val): (&str, i64) = parse_split_once(expr, "<");
-                rr.push((var.to_string(), '<', val, res.to_string()));
-            } else if s.contains('>') {
-                let (expr, res) = split1(s, ":");
-                let (var, val): (&str, i64) = parse_split_once(expr, ">");
-                rr.push((var.to_string(), '>', val, res.to_string()));
-            } else {
-                rr.push((s.to_string(), '.', 0, s.to_string()));
-            }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    X,
+    M,
+    A,
+    S,
+}
+
+impl Category {
+    pub fn from_name(s: &str) -> Self {
+        match s {
+            "x" => Category::X,
+            "m" => Category::M,
+            "a" => Category::A,
+            "s" => Category::S,
+            _ => unreachable!("bad category {}", s),
         }
-        rules.insert(name.to_string(), rr);
     }
-    rules
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
 }
 
-#[aoc(day19, part1)]
-pub fn part1(input: &str) -> i64 {
-    let (rules_str, parts_str) = split1(input, "\n\n");
+/// Ratings for x, m, a, s, in that order.
+pub type Part = [i64; 4];
+/// Inclusive rating ranges for x, m, a, s, in that order.
+pub type PartRange = [RangeInclusive<i64>; 4];
 
-    let rules = parse_rules(rules_str);
+fn non_empty(r: RangeInclusive<i64>) -> Option<RangeInclusive<i64>> {
+    if r.is_empty() {
+        None
+    } else {
+        Some(r)
+    }
+}
 
-    let mut parts = vec![];
-    for p in parts_str.lines() {
-        let p = p.trim_matches(|c| c == '{' || c == '}');
-        let mut d = HashMap::default();
-        let pairs = p.split(',');
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Condition {
+    Lt(Category, i64),
+    Gt(Category, i64),
+    Always,
+}
 
-        for pair in pairs {
-            let (label, val): (&str, i64) = parse_split_once(pair, "=");
-            d.insert(label, val);
+impl Condition {
+    /// Splits the parts in `r` into those which do and don't satisfy the
+    /// condition.
+    pub fn split(&self, r: &PartRange) -> (Option<PartRange>, Option<PartRange>) {
+        let (c, matched, rest) = match *self {
+            Condition::Always => return (Some(r.clone()), None),
+            Condition::Lt(c, v) => {
+                let range = &r[c.index()];
+                (
+                    c,
+                    *range.start()..=*range.end().min(&(v - 1)),
+                    *range.start().max(&v)..=*range.end(),
+                )
+            }
+            Condition::Gt(c, v) => {
+                let range = &r[c.index()];
+                (
+                    c,
+                    *range.start().max(&(v + 1))..=*range.end(),
+                    *range.start()..=*range.end().min(&v),
+                )
+            }
+        };
+        let with = |range| {
+            let mut r = r.clone();
+            r[c.index()] = range;
+            r
+        };
+        (non_empty(matched).map(with), non_empty(rest).map(with))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    Accept,
+    Reject,
+    Workflow(String),
+}
+
+impl Target {
+    fn from_name(s: &str) -> Self {
+        match s {
+            "A" => Target::Accept,
+            "R" => Target::Reject,
+            _ => Target::Workflow(s.to_string()),
         }
-        parts.push(d);
-    }
-
-    let mut sum = 0;
-    for p in parts {
-        let mut wf = "in";
-        'eval: while wf != "R" && wf != "A" {
-            let exprs = &rules[wf];
-            for (var, op, val, res) in exprs {
-                match op {
-                    '<' if p[var.as_str()] < *val => {
-                        wf = res;
-                        continue 'eval;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub condition: Condition,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workflow {
+    pub rules: Vec<Rule>,
+}
+
+/// A workflow, and the index of the rule in it which matched.
+pub type PathStep = (String, usize);
+
+/// A region of parts which all take the same path through the workflows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionOutcome {
+    pub region: PartRange,
+    pub path: Vec<PathStep>,
+    pub accepted: bool,
+}
+
+impl RegionOutcome {
+    pub fn volume(&self) -> i64 {
+        self.region
+            .iter()
+            .map(|r| r.end() - r.start() + 1)
+            .product()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    UndefinedTarget {
+        workflow: String,
+        rule: usize,
+    },
+    UnreachableRule {
+        workflow: String,
+        rule: usize,
+    },
+    /// Some parts don't match any of the workflow's rules.
+    FallsThrough {
+        workflow: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workflows {
+    pub workflows: HashMap<String, Workflow>,
+}
+
+pub const ENTRY: &str = "in";
+
+impl Workflows {
+    pub fn parse(rules_str: &str) -> Self {
+        let mut workflows = HashMap::default();
+        for r in rules_str.lines() {
+            let (name, r) = split1(r, "{");
+            let mut rules = vec![];
+            for s in r.trim_end_matches('}').split(',') {
+                let rule = if let Some((expr, res)) = s.split_once(':') {
+                    let condition = if expr.contains('<') {
+                        let (var, val): (&str, i64) = parse_split_once(expr, "<");
+                        Condition::Lt(Category::from_name(var), val)
+                    } else {
+                        let (var, val): (&str, i64) = parse_split_once(expr, ">");
+                        Condition::Gt(Category::from_name(var), val)
+                    };
+                    Rule {
+                        condition,
+                        target: Target::from_name(res),
                     }
-                    '>' if p[var.as_str()] > *val => {
-                        wf = res;
-                        continue 'eval;
+                } else {
+                    Rule {
+                        condition: Condition::Always,
+                        target: Target::from_name(s),
                     }
-                    '.' => {
-                        wf = res;
-                        continue 'eval;
+                };
+                rules.push(rule);
+            }
+            workflows.insert(name.to_string(), Workflow { rules });
+        }
+        Workflows { workflows }
+    }
+
+    /// Splits `region` into disjoint sub-regions which each follow a single
+    /// path through the workflows, starting at workflow `in`.
+    pub fn classify(&self, region: PartRange) -> Vec<RegionOutcome> {
+        let mut outcomes = vec![];
+        self.classify_target(
+            region,
+            &Target::Workflow(ENTRY.to_string()),
+            vec![],
+            &mut outcomes,
+        );
+        outcomes
+    }
+
+    fn classify_target(
+        &self,
+        region: PartRange,
+        target: &Target,
+        path: Vec<PathStep>,
+        outcomes: &mut Vec<RegionOutcome>,
+    ) {
+        match target {
+            Target::Accept | Target::Reject => outcomes.push(RegionOutcome {
+                region,
+                path,
+                accepted: *target == Target::Accept,
+            }),
+            Target::Workflow(wf) => {
+                let mut rest = Some(region);
+                for (idx, rule) in self.workflows[wf].rules.iter().enumerate() {
+                    let Some(r) = rest.take() else {
+                        break;
+                    };
+                    let (matched, unmatched) = rule.condition.split(&r);
+                    if let Some(m) = matched {
+                        let mut path = path.clone();
+                        path.push((wf.clone(), idx));
+                        self.classify_target(m, &rule.target, path, outcomes);
                     }
-                    _ => (),
+                    rest = unmatched;
+                }
+                // Parts which don't match any rule are rejected.
+                if let Some(region) = rest {
+                    outcomes.push(RegionOutcome {
+                        region,
+                        path,
+                        accepted: false,
+                    });
                 }
             }
         }
-        if wf == "A" {
-            sum += p.values().sum::<i64>()
+    }
+
+    /// The disjoint sub-regions of `region` which are accepted.
+    pub fn evaluate(&self, region: PartRange) -> Vec<RegionOutcome> {
+        let mut outcomes = self.classify(region);
+        outcomes.retain(|o| o.accepted);
+        outcomes
+    }
+
+    /// The path that a single part follows through the workflows.
+    pub fn trace(&self, part: &Part) -> RegionOutcome {
+        let mut outcomes = self.classify(part.map(|v| v..=v));
+        assert_eq!(outcomes.len(), 1);
+        outcomes.remove(0)
+    }
+
+    pub fn accepts(&self, part: &Part) -> bool {
+        self.trace(part).accepted
+    }
+
+    /// Checks every workflow for rules which can never match (given the
+    /// rules before them), targets which aren't defined, and parts which
+    /// don't match any rule.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = vec![];
+        let mut names = self.workflows.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let mut rest = Some([1..=4000, 1..=4000, 1..=4000, 1..=4000]);
+            for (idx, rule) in self.workflows[name].rules.iter().enumerate() {
+                if let Target::Workflow(t) = &rule.target {
+                    if !self.workflows.contains_key(t) {
+                        issues.push(ValidationIssue::UndefinedTarget {
+                            workflow: name.clone(),
+                            rule: idx,
+                        });
+                    }
+                }
+
+                let (matched, unmatched) = match &rest {
+                    Some(r) => rule.condition.split(r),
+                    None => (None, None),
+                };
+                if matched.is_none() {
+                    issues.push(ValidationIssue::UnreachableRule {
+                        workflow: name.clone(),
+                        rule: idx,
+                    });
+                }
+                rest = unmatched;
+            }
+            if rest.is_some() {
+                issues.push(ValidationIssue::FallsThrough {
+                    workflow: name.clone(),
+                });
+            }
         }
+
+        issues
     }
 
-    sum
-}
+    /// Removes unreachable rules, drops conditions which lead to the same
+    /// place as the fallthrough, and inlines workflows which always go to
+    /// the same target. Doesn't change which parts are accepted.
+    pub fn simplify(&self) -> Self {
+        let mut workflows = self.workflows.clone();
 
-#[aoc(day19, part2)]
-pub fn part2(input: &str) -> i64 {
-    let (rules_str, _) = split1(input, "\n\n");
-
-    let rules = parse_rules(rules_str);
-
-    let mut metapart = DefaultHashMap::new(1..=4000);
-    // Ensure that the default val exists for each.
-    metapart.get_mut("x".to_string());
-    metapart.get_mut("m".to_string());
-    metapart.get_mut("a".to_string());
-    metapart.get_mut("s".to_string());
-
-    fn recurse(
-        mut mp: DefaultHashMap<String, RangeInclusive<i64>>,
-        wf: &str,
-        rules: &HashMap<String, Vec<(String, char, i64, String)>>,
-    ) -> i64 {
-        if wf == "A" {
-            mp.values().map(|r| r.end() - r.start() + 1).product()
-        } else if wf == "R" {
-            0
-        } else {
-            let mut r = 0;
-            for (var, op, val, res) in &rules[wf] {
-                match op {
-                    '>' => {
-                        if mp[var].end() > val {
-                            let mut nmp = mp.clone();
-                            let (lower, upper) = split_interval_at(mp[var].clone(), *val);
-                            nmp[var.clone()] = upper;
-                            r += recurse(nmp, res, rules);
-                            mp[var.clone()] = lower;
-                        }
+        for issue in self.validate().into_iter().rev() {
+            if let ValidationIssue::UnreachableRule { workflow, rule } = issue {
+                workflows.get_mut(&workflow).unwrap().rules.remove(rule);
+            }
+        }
+
+        loop {
+            let mut changed = false;
+
+            for wf in workflows.values_mut() {
+                // `cond:T,T` is the same as `T`
+                while let [.., a, b] = &wf.rules[..] {
+                    if a.target != b.target || b.condition != Condition::Always {
+                        break;
                     }
-                    '<' => {
-                        if mp[var].start() < val {
-                            let mut nmp = mp.clone();
-                            let (lower, upper) = split_interval_at(mp[var].clone(), val - 1);
-                            nmp[var.clone()] = lower;
-                            r += recurse(nmp, res, rules);
-                            mp[var.clone()] = upper;
-                        }
+                    wf.rules.remove(wf.rules.len() - 2);
+                    changed = true;
+                }
+            }
+
+            let constant = workflows
+                .iter()
+                .filter(|(name, _)| name.as_str() != ENTRY)
+                .filter_map(|(name, wf)| match &wf.rules[..] {
+                    [Rule {
+                        condition: Condition::Always,
+                        target,
+                    }] => Some((name.clone(), target.clone())),
+                    _ => None,
+                })
+                .collect::<HashMap<_, _>>();
+
+            // Constant workflows can point at each other, so follow them
+            // until reaching one which isn't being removed.
+            let resolve = |t: &String| {
+                let mut t = t.clone();
+                for _ in 0..constant.len() {
+                    match constant.get(&t) {
+                        Some(Target::Workflow(next)) => t = next.clone(),
+                        Some(other) => return other.clone(),
+                        None => break,
                     }
-                    '.' => {
-                        r += recurse(mp.clone(), res, rules);
+                }
+                assert!(!constant.contains_key(&t), "workflow {} loops forever", t);
+                Target::Workflow(t)
+            };
+
+            for wf in workflows.values_mut() {
+                for rule in &mut wf.rules {
+                    if let Target::Workflow(t) = &rule.target {
+                        if constant.contains_key(t) {
+                            rule.target = resolve(t);
+                            changed = true;
+                        }
                     }
-                    _ => (),
                 }
             }
-            r
+            workflows.retain(|name, _| !constant.contains_key(name));
+
+            if !changed {
+                break;
+            }
         }
+
+        Workflows { workflows }
     }
+}
+
+fn parse_part(p: &str) -> Part {
+    let mut part = [0; 4];
+    for pair in p.trim_matches(|c| c == '{' || c == '}').split(',') {
+        let (label, val): (&str, i64) = parse_split_once(pair, "=");
+        part[Category::from_name(label).index()] = val;
+    }
+    part
+}
+
+#[aoc(day19, part1)]
+pub fn part1(input: &str) -> i64 {
+    let mut sections = Sections::new(input);
+    let workflows = Workflows::parse(&sections.next().unwrap().text());
 
-    recurse(metapart, "in", &rules)
+    sections
+        .next()
+        .unwrap()
+        .lines()
+        .map(parse_part)
+        .filter(|p| workflows.accepts(p))
+        .map(|p| p.iter().sum::<i64>())
+        .sum()
+}
+
+#[aoc(day19, part2)]
+pub fn part2(input: &str) -> i64 {
+    let rules = Sections::new(input).next().unwrap();
+    let workflows = Workflows::parse(&rules.text()).simplify();
+
+    workflows
+        .evaluate([1..=4000, 1..=4000, 1..=4000, 1..=4000])
+        .iter()
+        .map(RegionOutcome::volume)
+        .sum()
 }
 
 #[cfg(test)]
@@ -161,4 +432,92 @@ hdj{m>838:A,pv}
     fn part2_example() {
         assert_eq!(part2(EXAMPLE), 167409079868000);
     }
+
+    #[test]
+    fn simplify_example() {
+        let (rules_str, _) = split1(EXAMPLE, "\n\n");
+        let workflows = Workflows::parse(rules_str);
+        assert!(workflows.validate().is_empty());
+
+        // gd, lnx and then qs always end up at the same place
+        let simplified = workflows.simplify();
+        assert!(simplified.validate().is_empty());
+        for name in ["gd", "lnx", "qs"] {
+            assert!(!simplified.workflows.contains_key(name));
+        }
+        assert_eq!(simplified.workflows["qqz"].rules[0].target, Target::Accept);
+        assert_eq!(simplified.workflows.len(), 8);
+    }
+
+    #[test]
+    fn simplify_constant_chain() {
+        // w1 collapses to w2, which always rejects
+        let input = "in{m<3424:w1,w2}\nw1{a<2503:w2,a<1604:w2,w2}\nw2{R}";
+        let simplified = Workflows::parse(input).simplify();
+        assert_eq!(simplified.workflows.len(), 1);
+        assert_eq!(simplified.workflows[ENTRY].rules[0].target, Target::Reject);
+        assert_eq!(part2(&format!("{}\n\n{{x=1,m=2,a=3,s=4}}", input)), 0);
+    }
+
+    #[test]
+    fn validate_issues() {
+        let workflows = Workflows::parse("in{x<10:A,x<5:R,m>10:bar}\nfoo{A,R}");
+        assert_eq!(
+            workflows.validate(),
+            vec![
+                ValidationIssue::UnreachableRule {
+                    workflow: "foo".to_string(),
+                    rule: 1,
+                },
+                ValidationIssue::UnreachableRule {
+                    workflow: "in".to_string(),
+                    rule: 1,
+                },
+                ValidationIssue::UndefinedTarget {
+                    workflow: "in".to_string(),
+                    rule: 2,
+                },
+                ValidationIssue::FallsThrough {
+                    workflow: "in".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_part() {
+        let (rules_str, parts_str) = split1(EXAMPLE, "\n\n");
+        let workflows = Workflows::parse(rules_str);
+
+        let outcome = workflows.trace(&parse_part(parts_str.lines().next().unwrap()));
+        assert!(outcome.accepted);
+        assert_eq!(
+            outcome.path,
+            [("in", 1), ("qqz", 0), ("qs", 1), ("lnx", 0)].map(|(wf, idx)| (wf.to_string(), idx))
+        );
+        assert_eq!(outcome.region[0], 787..=787);
+    }
+
+    #[test]
+    fn classify_is_disjoint() {
+        let (rules_str, _) = split1(EXAMPLE, "\n\n");
+        let workflows = Workflows::parse(rules_str);
+
+        let outcomes = workflows.classify([1..=4000, 1..=4000, 1..=4000, 1..=4000]);
+        assert_eq!(
+            outcomes.iter().map(RegionOutcome::volume).sum::<i64>(),
+            4000i64.pow(4)
+        );
+        for (idx, a) in outcomes.iter().enumerate() {
+            for b in outcomes.iter().skip(idx + 1) {
+                assert!(!(0..4).all(|c| overlaps(a.region[c].clone(), b.region[c].clone())));
+            }
+        }
+
+        // Every part in an accepted region is accepted along the same path.
+        for o in outcomes.iter().filter(|o| o.accepted) {
+            let corner = o.region.clone().map(|r| *r.end());
+            assert_eq!(workflows.trace(&corner).path, o.path);
+        }
+    }
 }
@@ -2,8 +2,8 @@ use petgraph::graph::NodeIndex;
 
 use crate::prelude::*;
 
-#[derive(Debug)]
-enum Module {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Module {
     Broadcaster,
     Flipflop(bool),
     Conjunction(DefaultHashMap<NodeIndex, bool>),
@@ -38,128 +38,414 @@ fn build_graph(input: &str) -> (Graph<Module, ()>, HashMap<&'_ str, NodeIndex>)
     (g, name)
 }
 
-#[aoc(day20, part1)]
-pub fn part1(input: &str) -> i64 {
-    let (mut g, name) = build_graph(input);
+/// A single pulse sent from one module to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pulse {
+    pub from: NodeIndex,
+    pub to: NodeIndex,
+    pub high: bool,
+}
 
-    // Press the button once -> send a low pulse to broadcaster
-    let mut num_low_pulses = 0;
-    let mut num_high_pulses = 0;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PulseStats {
+    pub low: i64,
+    pub high: i64,
+}
 
-    for _ in 0..1000 {
+/// The state of every module in a [`Circuit`], from [`Circuit::snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CircuitState {
+    modules: Vec<Module>,
+    presses: i64,
+}
+
+pub struct Circuit<'a> {
+    graph: Graph<Module, ()>,
+    names: HashMap<&'a str, NodeIndex>,
+    inputs: Vec<Vec<NodeIndex>>,
+    presses: i64,
+}
+
+impl<'a> Circuit<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let (graph, names) = build_graph(input);
+        let inputs = graph
+            .node_indices()
+            .map(|n| {
+                graph
+                    .edges_directed(n, Incoming)
+                    .map(|e| e.source())
+                    .collect()
+            })
+            .collect();
+
+        Circuit {
+            graph,
+            names,
+            inputs,
+            presses: 0,
+        }
+    }
+
+    pub fn graph(&self) -> &Graph<Module, ()> {
+        &self.graph
+    }
+
+    pub fn node(&self, name: &str) -> NodeIndex {
+        self.names[name]
+    }
+
+    pub fn name(&self, node: NodeIndex) -> &'a str {
+        self.names
+            .iter()
+            .find(|(_, n)| **n == node)
+            .map(|(name, _)| *name)
+            .unwrap()
+    }
+
+    /// Modules which send pulses to `node`.
+    pub fn inputs(&self, node: NodeIndex) -> &[NodeIndex] {
+        &self.inputs[node.index()]
+    }
+
+    /// Number of times the button has been pressed so far.
+    pub fn presses(&self) -> i64 {
+        self.presses
+    }
+
+    /// Press the button once -> send a low pulse to broadcaster
+    pub fn press_button(&mut self) -> PulseStats {
+        self.press_button_with(|_| ())
+    }
+
+    /// Like [`Circuit::press_button`], but calls `on_pulse` for every pulse
+    /// as it's delivered, including the initial pulse from the button.
+    pub fn press_button_with(&mut self, mut on_pulse: impl FnMut(&Pulse)) -> PulseStats {
+        self.presses += 1;
+        let mut stats = PulseStats::default();
+
+        let broadcaster = self.names["broadcaster"];
         let mut pulses = VecDeque::new();
-        pulses.push_back((name["broadcaster"], name["broadcaster"], false));
+        pulses.push_back(Pulse {
+            from: broadcaster,
+            to: broadcaster,
+            high: false,
+        });
 
-        while let Some((from, node, pulse_val)) = pulses.pop_front() {
-            if pulse_val {
-                num_low_pulses += 1;
+        while let Some(pulse) = pulses.pop_front() {
+            on_pulse(&pulse);
+            if pulse.high {
+                stats.high += 1;
             } else {
-                num_high_pulses += 1;
+                stats.low += 1;
             }
 
-            let incoming = g
-                .edges_directed(node, Incoming)
-                .map(|e| e.source())
-                .collect::<Vec<_>>();
-
-            let output = match g.node_weight_mut(node).unwrap() {
-                Module::Broadcaster => Some(pulse_val),
+            let Pulse { from, to, high } = pulse;
+            let output = match self.graph.node_weight_mut(to).unwrap() {
+                Module::Broadcaster => Some(high),
                 Module::Conjunction(ref mut received) => {
-                    received[from] = pulse_val;
-
-                    if incoming.iter().all(|i| received[i]) {
-                        Some(false)
-                    } else {
-                        Some(true)
-                    }
+                    received[from] = high;
+                    Some(!self.inputs[to.index()].iter().all(|i| received[i]))
                 }
-                Module::Flipflop(_) if pulse_val => None,
+                Module::Flipflop(_) if high => None,
                 Module::Flipflop(ref mut x) => {
                     *x ^= true;
                     Some(*x)
                 }
                 Module::Unknown => None,
             };
-            if let Some(pulse) = output {
-                for recipient in g.edges_directed(node, Outgoing) {
-                    pulses.push_back((node, recipient.target(), pulse));
+            if let Some(high) = output {
+                for recipient in self.graph.edges_directed(to, Outgoing) {
+                    pulses.push_back(Pulse {
+                        from: to,
+                        to: recipient.target(),
+                        high,
+                    });
                 }
             }
         }
+
+        stats
+    }
+
+    pub fn snapshot(&self) -> CircuitState {
+        CircuitState {
+            modules: self.graph.node_weights().cloned().collect(),
+            presses: self.presses,
+        }
+    }
+
+    pub fn restore(&mut self, state: &CircuitState) {
+        for (m, s) in self.graph.node_weights_mut().zip(&state.modules) {
+            *m = s.clone();
+        }
+        self.presses = state.presses;
     }
 
-    num_low_pulses * num_high_pulses
+    /// Renders the module graph in Graphviz DOT format. Flip-flops are boxes,
+    /// conjunctions are diamonds.
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n".to_string();
+        for n in self.graph.node_indices() {
+            let shape = match self.graph[n] {
+                Module::Broadcaster => "doublecircle",
+                Module::Flipflop(_) => "box",
+                Module::Conjunction(_) => "diamond",
+                Module::Unknown => "ellipse",
+            };
+            dot += &format!(
+                "    {} [label=\"{}\" shape={}]\n",
+                n.index(),
+                self.name(n),
+                shape
+            );
+        }
+        for e in self.graph.edge_references() {
+            dot += &format!("    {} -> {}\n", e.source().index(), e.target().index());
+        }
+        dot += "}\n";
+        dot
+    }
 }
 
-#[aoc(day20, part2)]
-pub fn part2(input: &str) -> i64 {
-    let (mut g, name) = build_graph(input);
-
-    // find the important nodes -- the node immediately prior to the rx is a
-    // conjunction, so it's all the inputs to the conjunction
-    let mut important = HashSet::default();
-    for src in g.edges_directed(name["rx"], Incoming).map(|e| e.source()) {
-        let incoming = g
-            .edges_directed(src, Incoming)
-            .map(|e| e.source())
-            .collect::<Vec<_>>();
-        assert!(important.is_empty());
-        important.extend(incoming);
+/// A chain of flip-flops which counts button presses in binary, and a hub
+/// conjunction which emits a low pulse (and resets the chain) once the count
+/// reaches `period`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryCounter {
+    /// Flip-flops from the least to the most significant bit.
+    pub bits: Vec<NodeIndex>,
+    pub hub: NodeIndex,
+    pub period: i64,
+}
+
+impl BinaryCounter {
+    pub fn value(&self, circuit: &Circuit) -> i64 {
+        self.bits
+            .iter()
+            .enumerate()
+            .map(|(i, b)| match circuit.graph()[*b] {
+                Module::Flipflop(true) => 1 << i,
+                _ => 0,
+            })
+            .sum()
     }
 
-    let mut prev: HashMap<_, i64> = HashMap::default();
-    let mut cyc = HashMap::default();
+    /// Number of presses from now until the hub next emits a low pulse.
+    pub fn presses_until_reset(&self, circuit: &Circuit) -> i64 {
+        let v = self.value(circuit);
+        if v < self.period {
+            self.period - v
+        } else {
+            (1 << self.bits.len()) - v + self.period
+        }
+    }
+}
 
-    for idx in 1.. {
-        let mut pulses = VecDeque::new();
-        pulses.push_back((name["broadcaster"], name["broadcaster"], false));
+/// One of the independent parts of the circuit which feeds the final
+/// conjunction in front of `rx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubCircuit {
+    /// The module which sends pulses to the final conjunction.
+    pub output: NodeIndex,
+    /// Every module upstream of `output` other than the broadcaster.
+    pub modules: HashSet<NodeIndex>,
+    pub counter: Option<BinaryCounter>,
+}
 
-        while let Some((from, node, pulse_val)) = pulses.pop_front() {
-            if !pulse_val {
-                if prev.contains_key(&node) && !cyc.contains_key(&node) && important.contains(&node)
-                {
-                    cyc.insert(node, idx - prev[&node]);
-                }
+/// `node` emits a high pulse on press `first`, and every `period` presses
+/// after that.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputCycle {
+    pub node: NodeIndex,
+    pub first: i64,
+    pub period: i64,
+}
 
-                prev.insert(node, idx);
-            }
+/// Splits the circuit into the independent sub-circuits feeding the
+/// conjunction in front of `rx`. Returns `None` if `rx` isn't fed by a single
+/// conjunction, or if the sub-circuits share any modules.
+pub fn decompose(circuit: &Circuit) -> Option<(NodeIndex, Vec<SubCircuit>)> {
+    let g = circuit.graph();
+    let broadcaster = circuit.node("broadcaster");
 
-            if cyc.len() == important.len() {
-                return cyc.values().copied().reduce(lcm).unwrap();
+    let last = match circuit.inputs(circuit.node("rx")) {
+        [last] if matches!(g[*last], Module::Conjunction(_)) => *last,
+        _ => return None,
+    };
+
+    let mut seen = HashSet::default();
+    let mut subcircuits = vec![];
+    for output in circuit.inputs(last) {
+        let mut modules = HashSet::default();
+        let mut stk = vec![*output];
+        while let Some(n) = stk.pop() {
+            if n != broadcaster && modules.insert(n) {
+                stk.extend(circuit.inputs(n).iter().copied());
             }
+        }
+        if modules.contains(&last) || modules.iter().any(|m| !seen.insert(*m)) {
+            return None;
+        }
 
-            let incoming = g
-                .edges_directed(node, Incoming)
-                .map(|e| e.source())
-                .collect::<Vec<_>>();
+        let counter = detect_counter(circuit, &modules, *output);
+        subcircuits.push(SubCircuit {
+            output: *output,
+            modules,
+            counter,
+        });
+    }
 
-            let output = match g.node_weight_mut(node).unwrap() {
-                Module::Broadcaster => Some(pulse_val),
-                Module::Conjunction(ref mut received) => {
-                    received[from] = pulse_val;
+    Some((last, subcircuits))
+}
 
-                    if incoming.iter().all(|i| received[i]) {
-                        Some(false)
-                    } else {
-                        Some(true)
-                    }
-                }
-                Module::Flipflop(_) if pulse_val => None,
-                Module::Flipflop(ref mut x) => {
-                    *x ^= true;
-                    Some(*x)
-                }
-                Module::Unknown => None,
-            };
-            if let Some(pulse) = output {
-                for recipient in g.edges_directed(node, Outgoing) {
-                    pulses.push_back((node, recipient.target(), pulse));
+/// Recognizes the usual shape of a sub-circuit: a [`BinaryCounter`] whose hub
+/// is inverted into `output`.
+fn detect_counter(
+    circuit: &Circuit,
+    modules: &HashSet<NodeIndex>,
+    output: NodeIndex,
+) -> Option<BinaryCounter> {
+    let g = circuit.graph();
+    let is_flipflop = |n: &NodeIndex| matches!(g[*n], Module::Flipflop(_));
+
+    let hub = match (&g[output], circuit.inputs(output)) {
+        (Module::Conjunction(_), [hub]) if matches!(g[*hub], Module::Conjunction(_)) => *hub,
+        _ => return None,
+    };
+
+    let mut bit = g
+        .neighbors(circuit.node("broadcaster"))
+        .find(|n| modules.contains(n) && is_flipflop(n))?;
+    let mut bits = vec![];
+    let mut period = 0;
+
+    loop {
+        if bits.contains(&bit) {
+            return None;
+        }
+        bits.push(bit);
+
+        let outputs = g.neighbors(bit).collect::<Vec<_>>();
+        // Bits which are set in the period feed the hub, and the others are
+        // set by the hub when it resets the counter.
+        if outputs.contains(&hub) {
+            period |= 1 << (bits.len() - 1);
+        }
+
+        let next = outputs
+            .iter()
+            .filter(|n| is_flipflop(n))
+            .collect::<Vec<_>>();
+        if outputs.iter().any(|n| *n != hub && !is_flipflop(n)) {
+            return None;
+        }
+        match next[..] {
+            [] => break,
+            [n] => bit = *n,
+            _ => return None,
+        }
+    }
+
+    // The hub resets exactly the lowest bit and the bits which are clear in
+    // the period, and there can't be anything else in the sub-circuit.
+    let reset = g.neighbors(hub).filter(is_flipflop).collect::<HashSet<_>>();
+    let expected = bits
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i == 0 || period & (1 << i) == 0)
+        .map(|(_, b)| *b)
+        .collect::<HashSet<_>>();
+    if reset != expected || modules.len() != bits.len() + 2 {
+        return None;
+    }
+
+    Some(BinaryCounter { bits, hub, period })
+}
+
+/// Finds when each sub-circuit's output emits a high pulse, analytically for
+/// binary counters and by simulating otherwise. The circuit is left in its
+/// original state.
+pub fn output_cycles(circuit: &mut Circuit, subcircuits: &[SubCircuit]) -> Vec<OutputCycle> {
+    let snapshot = circuit.snapshot();
+    let mut cycles = vec![];
+
+    for sc in subcircuits {
+        if let Some(counter) = &sc.counter {
+            cycles.push(OutputCycle {
+                node: sc.output,
+                first: circuit.presses() + counter.presses_until_reset(circuit),
+                period: counter.period,
+            });
+            continue;
+        }
+
+        let mut seen = vec![];
+        while seen.len() < 2 {
+            let idx = circuit.presses() + 1;
+            circuit.press_button_with(|pulse| {
+                if pulse.high && pulse.from == sc.output && seen.last() != Some(&idx) {
+                    seen.push(idx);
                 }
-            }
+            });
+        }
+        circuit.restore(&snapshot);
+
+        cycles.push(OutputCycle {
+            node: sc.output,
+            first: seen[0],
+            period: seen[1] - seen[0],
+        });
+    }
+
+    cycles
+}
+
+/// Presses the button until `rx` receives a low pulse.
+fn simulate_until_rx(circuit: &mut Circuit) -> i64 {
+    let rx = circuit.node("rx");
+    loop {
+        let mut done = false;
+        circuit.press_button_with(|pulse| done |= pulse.to == rx && !pulse.high);
+        if done {
+            return circuit.presses();
         }
     }
+}
+
+#[aoc(day20, part1)]
+pub fn part1(input: &str) -> i64 {
+    let mut circuit = Circuit::new(input);
 
-    unreachable!()
+    let mut total = PulseStats::default();
+    for _ in 0..1000 {
+        let stats = circuit.press_button();
+        total.low += stats.low;
+        total.high += stats.high;
+    }
+
+    total.low * total.high
+}
+
+#[aoc(day20, part2)]
+pub fn part2(input: &str) -> i64 {
+    let mut circuit = Circuit::new(input);
+
+    let Some((_, subcircuits)) = decompose(&circuit) else {
+        return simulate_until_rx(&mut circuit);
+    };
+
+    // rx gets a low pulse once all of the sub-circuits emit a high pulse
+    // during the same press.
+    let cycles = output_cycles(&mut circuit, &subcircuits);
+    let latest_first = cycles.iter().map(|c| c.first).max().unwrap();
+    let (x, l) = cycles
+        .iter()
+        .map(|c| (c.first.rem_euclid(c.period), c.period))
+        .try_fold((0, 1), crt)
+        .expect("sub-circuits never line up");
+
+    x + l * (((latest_first - x).max(0) + l - 1) / l)
 }
 
 #[cfg(test)]
@@ -172,8 +458,113 @@ mod tests {
 %c -> inv
 &inv -> a"#;
 
+    const EXAMPLE2: &str = r#"broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output"#;
+
     #[test]
     fn part1_example() {
         assert_eq!(part1(EXAMPLE), 32000000);
+        assert_eq!(part1(EXAMPLE2), 11687500);
+    }
+
+    #[test]
+    fn circuit_snapshot() {
+        let mut circuit = Circuit::new(EXAMPLE2);
+        let initial = circuit.snapshot();
+
+        let stats = circuit.press_button();
+        assert_eq!(stats, PulseStats { low: 4, high: 4 });
+
+        let mut emitted = vec![];
+        let after_one = circuit.snapshot();
+        circuit.press_button_with(|p| emitted.push((p.from, p.high)));
+        assert_eq!(emitted[1], (circuit.node("broadcaster"), false));
+        assert!(emitted.contains(&(circuit.node("a"), false)));
+
+        circuit.restore(&after_one);
+        assert_eq!(circuit.presses(), 1);
+        circuit.restore(&initial);
+        assert_eq!(circuit.snapshot(), initial);
+    }
+
+    #[test]
+    fn circuit_dot() {
+        let dot = Circuit::new(EXAMPLE2).to_dot();
+        assert!(dot.contains("[label=\"a\" shape=box]"));
+        assert!(dot.contains("[label=\"con\" shape=diamond]"));
+        assert!(dot.contains("[label=\"output\" shape=ellipse]"));
+    }
+
+    const COUNTERS: &str = r#"broadcaster -> a0, b0
+%a0 -> a1, ha
+%a1 -> a2
+%a2 -> a3, ha
+%a3 -> ha
+&ha -> a0, a1, ia
+&ia -> fin
+%b0 -> b1, hb
+%b1 -> b2, hb
+%b2 -> b3
+%b3 -> hb
+&hb -> b0, b2, ib
+&ib -> fin
+&fin -> rx"#;
+
+    #[test]
+    fn decompose_counters() {
+        let circuit = Circuit::new(COUNTERS);
+        let (last, subcircuits) = decompose(&circuit).unwrap();
+        assert_eq!(last, circuit.node("fin"));
+        let mut periods = subcircuits
+            .iter()
+            .map(|sc| sc.counter.as_ref().unwrap().period)
+            .collect::<Vec<_>>();
+        periods.sort();
+        assert_eq!(periods, vec![11, 13]);
+
+        assert_eq!(part2(COUNTERS), 143);
+        assert_eq!(simulate_until_rx(&mut Circuit::new(COUNTERS)), 143);
+    }
+
+    #[test]
+    fn output_cycles_fallback() {
+        let mut circuit = Circuit::new(COUNTERS);
+        for _ in 0..5 {
+            circuit.press_button();
+        }
+        let (_, mut subcircuits) = decompose(&circuit).unwrap();
+        let analytic = output_cycles(&mut circuit, &subcircuits);
+
+        for sc in &mut subcircuits {
+            sc.counter = None;
+        }
+        let simulated = output_cycles(&mut circuit, &subcircuits);
+        assert_eq!(analytic, simulated);
+        let ia = analytic
+            .iter()
+            .find(|c| c.node == circuit.node("ia"))
+            .unwrap();
+        assert_eq!((ia.first, ia.period), (13, 13));
+        assert_eq!(circuit.presses(), 5);
+    }
+
+    #[test]
+    fn near_miss_counter() {
+        // The hub also resets a2, which is set in the period, so this isn't a
+        // binary counter.
+        let input = COUNTERS.replace("&ha -> a0, a1, ia", "&ha -> a0, a1, a2, ia");
+        let circuit = Circuit::new(&input);
+        let (_, subcircuits) = decompose(&circuit).unwrap();
+        let a = subcircuits
+            .iter()
+            .find(|sc| sc.output == circuit.node("ia"))
+            .unwrap();
+        assert_eq!(a.counter, None);
+
+        assert_eq!(part2(&input), 22);
+        assert_eq!(simulate_until_rx(&mut Circuit::new(&input)), 22);
     }
 }
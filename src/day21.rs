@@ -35,60 +35,47 @@ pub fn part1(input: &str, target: i64) -> i64 {
 }
 
 #[aoc(day21, part2)]
-pub fn part2(input: &str) -> i64 {
-    let g = parse_char_grid(input);
-    let (i_bounds, j_bounds) = get_grid_bounds(&g);
-
-    let (start_pos, _) = g.iter().find(|(_, c)| **c == 'S').unwrap();
-
-    let mut queue = VecDeque::new();
-    queue.push_back((*start_pos, 0));
-
-    let mut visited: DefaultHashMap<i64, HashSet<(i64, i64)>> = DefaultHashMap::default();
-
-    visited[0].insert(*start_pos);
-
-    let target: i64 = 26501365;
-    let offset = target % i_bounds.end;
-
-    while let Some((n, step)) = queue.pop_front() {
-        // if we got to `step`, we've finished `step-1`. Do some cleanup
-        if step == offset + 2 * i_bounds.end + 1 {
-            break;
-        }
-        if step == target {
-            continue;
-        }
-
-        for x in adjacents(n, FOUR_WAY) {
-            let xx = (x.0.rem_euclid(i_bounds.end), x.1.rem_euclid(j_bounds.end));
-
-            if visited[step + 1].is_empty() {
-                let p = visited[step - 1].clone();
-                visited.insert(step + 1, p);
-            }
+pub fn part2_in(input: &str) -> i64 {
+    part2(input, 26501365, 10)
+}
 
-            if (g[xx] == '.' || g[xx] == 'S') && !visited[step + 1].contains(&x) {
-                visited[step + 1].insert(x);
-                queue.push_back((x, step + 1));
-            }
-        }
+/// Number of samples held back to check the recurrence against.
+const CHECK_SAMPLES: usize = 2;
+
+/// The number of plots reachable in exactly `target` steps is sampled every
+/// tile width, and the shortest linear recurrence through those counts is
+/// extended out to `target`. Berlekamp-Massey can only pin down a recurrence
+/// of order d from 2d values, so this finds recurrences of order up to
+/// `(samples - 2) / 2`, and panics unless the recurrence also predicts the
+/// last two samples.
+pub fn part2(input: &str, target: i64, samples: i64) -> i64 {
+    let g = TiledGrid::new(parse_char_grid(input));
+    let (n, _) = g.tile_size();
+
+    let (start_pos, _) = g.grid().iter().find(|(_, c)| **c == 'S').unwrap();
+
+    let offset = target % n;
+    let bfs = g.bfs(*start_pos, offset + (samples - 1) * n, |c| {
+        c == '.' || c == 'S'
+    });
+
+    let counts = (0..samples)
+        .map(|k| BigRational::from_integer(bfs.count_frontier(offset + k * n).into()))
+        .collect::<Vec<_>>();
+    let (fit, check) = counts.split_at(counts.len() - CHECK_SAMPLES);
+    let recurrence = berlekamp_massey(fit);
+    for (k, expected) in check.iter().enumerate() {
+        assert_eq!(
+            recurrence.nth((fit.len() + k) as u64),
+            *expected,
+            "counts don't follow a recurrence of order at most {}",
+            fit.len() / 2
+        );
     }
 
-    polynomial_regression(
-        &[
-            offset,
-            offset + i_bounds.end,
-            offset + i_bounds.end + i_bounds.end,
-        ],
-        &[
-            visited[offset].len() as i64,
-            visited[offset + i_bounds.end].len() as i64,
-            visited[offset + i_bounds.end + i_bounds.end].len() as i64,
-        ],
-        2,
-    )
-    .eval(target)
+    let count = recurrence.nth((target / n) as u64);
+    assert!(count.is_integer());
+    count.to_integer().try_into().unwrap()
 }
 
 #[cfg(test)]
@@ -111,4 +98,9 @@ mod tests {
     fn part1_example() {
         assert_eq!(part1(EXAMPLE, 6), 16);
     }
+
+    #[test]
+    fn part2_example() {
+        assert_eq!(part2(EXAMPLE, 5000, 30), 16733044);
+    }
 }
@@ -45,55 +45,6 @@ pub fn part1(input: &str) -> i64 {
 
 type P = (i64, i64);
 
-/// Simplify the graph by following the hallways and making them into long
-/// edges.
-fn simplified_graph(g: &IGrid2D) -> HashMap<P, Vec<(P, i64)>> {
-    let (i_bounds, j_bounds) = get_grid_bounds(g);
-    let mut junctions = HashMap::default();
-
-    for i in i_bounds.clone() {
-        for j in j_bounds.clone() {
-            let p = (i, j);
-            if g[p] == '.' {
-                let exits = adjacents(p, FOUR_WAY).filter(|n| g[n] == '.').count();
-                if exits != 2 {
-                    junctions.insert(p, vec![]);
-                }
-            }
-        }
-    }
-
-    for j in junctions.keys().copied().collect::<Vec<_>>() {
-        let mut edges = vec![];
-
-        for n in adjacents(j, FOUR_WAY) {
-            let mut visited = HashSet::<P>::default();
-            visited.insert(j);
-
-            let mut q = vec![(n, 1)];
-
-            while let Some((n, s)) = q.pop() {
-                visited.insert(n);
-
-                if junctions.contains_key(&n) {
-                    edges.push((n, s));
-                    break;
-                }
-
-                for nn in adjacents(n, FOUR_WAY) {
-                    if !visited.contains(&nn) && g[nn] == '.' {
-                        q.push((nn, s + 1));
-                    }
-                }
-            }
-        }
-
-        junctions.get_mut(&j).unwrap().extend(edges);
-    }
-
-    junctions
-}
-
 #[aoc_generator(day23, part2)]
 pub fn part2_gen(input: &str) -> (usize, usize, Vec<Vec<(usize, i64)>>) {
     let mut g = parse_char_grid(input);
@@ -117,81 +68,47 @@ pub fn part2_gen(input: &str) -> (usize, usize, Vec<Vec<(usize, i64)>>) {
         }
     }
 
-    let mut s = simplified_graph(&g);
+    let JunctionGraph {
+        mut graph,
+        start,
+        end,
+        ..
+    } = grid_to_junction_graph(&g, |c| c == '.', start_pos, end_pos);
 
     // Slightly trim the graph depth -- there's only one path to the end, so we
     // can collapse the end by one node.
-    let mut paths_to_end = s
-        .iter()
-        .filter(|(_, v)| v.iter().any(|(a, _)| *a == end_pos))
+    let mut paths_to_end = graph
+        .edges_directed(end, Incoming)
+        .map(|e| (e.source(), *e.weight()))
         .collect::<Vec<_>>();
     assert_eq!(paths_to_end.len(), 1);
-    let (new_end_pos, next) = paths_to_end.remove(0);
-    let new_end_pos = *new_end_pos;
-    let extra_cost = next
-        .iter()
-        .filter(|(p, _)| *p == end_pos)
-        .map(|(_, c)| *c)
-        .next()
-        .unwrap();
-    s.remove(&end_pos);
-    for v in s.values_mut() {
-        for (p, c) in v {
-            if *p == new_end_pos {
-                *c += extra_cost;
-            }
-        }
+    let (new_end, extra_cost) = paths_to_end.remove(0);
+    graph.retain_edges(|gr, e| gr.edge_endpoints(e).unwrap().0 != new_end);
+    let into_new_end = graph
+        .edges_directed(new_end, Incoming)
+        .map(|e| e.id())
+        .collect::<Vec<_>>();
+    for e in into_new_end {
+        graph[e] += extra_cost;
     }
-    s.get_mut(&new_end_pos).unwrap().clear();
-    end_pos = new_end_pos;
 
     // Rewrite the graph using integer nodes for faster runtime
-    let keys = s.keys().collect::<Vec<_>>();
-    let mut graf = Vec::with_capacity(keys.len());
-
-    for k in &keys {
-        let v = &s[k];
-        graf.push(
-            v.iter()
-                .map(|(p, c)| (keys.iter().position(|x| *x == p).unwrap(), *c))
-                .collect::<Vec<_>>(),
-        );
-    }
-
-    let start = keys.iter().position(|x| **x == start_pos).unwrap();
-    let end = keys.iter().position(|x| **x == end_pos).unwrap();
+    let graf = graph
+        .node_indices()
+        .map(|n| {
+            graph
+                .edges(n)
+                .map(|e| (e.target().index(), *e.weight()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
 
-    (start, end, graf)
+    (start.index(), new_end.index(), graf)
 }
 
 #[aoc(day23, part2)]
 pub fn part2((start, end, graf): &(usize, usize, Vec<Vec<(usize, i64)>>)) -> i64 {
-    fn dfs(
-        s: &[Vec<(usize, i64)>],
-        pos: usize,
-        steps: i64,
-        mut visited: u64,
-        end_pos: usize,
-    ) -> Option<i64> {
-        if pos == end_pos {
-            Some(steps)
-        } else {
-            visited |= 1 << pos;
-            let mut b = None;
-            for (next, st) in &s[pos] {
-                if visited & (1 << *next) == 0 {
-                    if let Some(v) = dfs(s, *next, steps + st, visited, end_pos) {
-                        if b.map(|vv| vv < v).unwrap_or(true) {
-                            b = Some(v);
-                        }
-                    }
-                }
-            }
-            b
-        }
-    }
-
-    dfs(graf, *start, 0, 0, *end).unwrap()
+    longest_simple_path(graf, *start, *end, true).unwrap()
 }
 
 #[cfg(test)]
@@ -0,0 +1,190 @@
+use std::rc::Rc;
+
+use crate::prelude::*;
+
+/// A beam at a position, travelling in a direction.
+pub type Beam = (Point, Point);
+
+/// Maps a character and an incoming beam direction to the outgoing beam
+/// directions. Characters which aren't in the table let beams pass straight
+/// through.
+#[derive(Debug, Clone, Default)]
+pub struct OpticsTable {
+    table: HashMap<(char, Point), Vec<Point>>,
+}
+
+impl OpticsTable {
+    pub fn insert(&mut self, ch: char, incoming: Point, outgoing: Vec<Point>) {
+        self.table.insert((ch, incoming), outgoing);
+    }
+
+    /// Returns `None` if the beam passes straight through.
+    pub fn outgoing(&self, ch: char, incoming: Point) -> Option<&[Point]> {
+        self.table.get(&(ch, incoming)).map(|v| v.as_slice())
+    }
+
+    /// `/` and `\` are mirrors, `|` and `-` split beams which hit their flat
+    /// side.
+    pub fn mirrors_and_splitters() -> Self {
+        let mut t = OpticsTable::default();
+        for (a, b) in [(RIGHT, UP), (LEFT, DOWN), (UP, RIGHT), (DOWN, LEFT)] {
+            t.insert('/', a, vec![b]);
+        }
+        for (a, b) in [(RIGHT, DOWN), (LEFT, UP), (UP, LEFT), (DOWN, RIGHT)] {
+            t.insert('\\', a, vec![b]);
+        }
+        for d in [LEFT, RIGHT] {
+            t.insert('|', d, vec![UP, DOWN]);
+        }
+        for d in [UP, DOWN] {
+            t.insert('-', d, vec![LEFT, RIGHT]);
+        }
+        t
+    }
+}
+
+/// The beams between entering the grid (or leaving an optical element) and
+/// the next optical element.
+#[derive(Debug)]
+struct Segment {
+    beams: Vec<Beam>,
+    exits: Vec<Beam>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeamTrace {
+    /// Every (position, incoming direction) which a beam passed through.
+    pub visited: HashSet<Beam>,
+    /// Beams at which the light re-entered a path it was already on.
+    pub loops: Vec<Beam>,
+}
+
+impl BeamTrace {
+    /// Number of distinct cells with a beam in them.
+    pub fn energized(&self) -> usize {
+        self.visited
+            .iter()
+            .map(|b| b.0)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Traces beams through a grid. Segments are cached, so tracing many entry
+/// beams through the same grid only walks each path once.
+pub struct BeamTracer<'a> {
+    grid: &'a IGrid2D,
+    optics: OpticsTable,
+    segments: HashMap<Beam, Rc<Segment>>,
+}
+
+impl<'a> BeamTracer<'a> {
+    pub fn new(grid: &'a IGrid2D, optics: OpticsTable) -> Self {
+        BeamTracer {
+            grid,
+            optics,
+            segments: HashMap::default(),
+        }
+    }
+
+    fn segment(&mut self, start: Beam) -> Rc<Segment> {
+        if let Some(s) = self.segments.get(&start) {
+            return s.clone();
+        }
+
+        let mut beams = vec![];
+        let (mut pos, dir) = start;
+        let exits = loop {
+            beams.push((pos, dir));
+            match self.optics.outgoing(self.grid[pos], dir) {
+                Some(outgoing) => {
+                    break outgoing
+                        .iter()
+                        .map(|d| (point_add(pos, *d), *d))
+                        .filter(|(p, _)| self.grid.contains_key(p))
+                        .collect();
+                }
+                None => {
+                    pos = point_add(pos, dir);
+                    if !self.grid.contains_key(&pos) {
+                        break vec![];
+                    }
+                }
+            }
+        };
+
+        let s = Rc::new(Segment { beams, exits });
+        self.segments.insert(start, s.clone());
+        s
+    }
+
+    /// Follows `start` and all of the beams it splits into until they leave
+    /// the grid or loop.
+    pub fn trace(&mut self, start: Beam) -> BeamTrace {
+        let mut visited = HashSet::default();
+        let mut loops = vec![];
+        if !self.grid.contains_key(&start.0) {
+            return BeamTrace { visited, loops };
+        }
+
+        // DFS over segments; `true` means the segment is still on the stack.
+        let mut on_stack = HashMap::default();
+        let mut stk = vec![(start, self.segment(start), 0)];
+        on_stack.insert(start, true);
+        visited.extend(stk[0].1.beams.iter().copied());
+
+        while let Some((beam, seg, idx)) = stk.last_mut() {
+            if let Some(next) = seg.exits.get(*idx).copied() {
+                *idx += 1;
+                match on_stack.get(&next) {
+                    Some(true) => loops.push(next),
+                    Some(false) => (),
+                    None => {
+                        let s = self.segment(next);
+                        visited.extend(s.beams.iter().copied());
+                        on_stack.insert(next, true);
+                        stk.push((next, s, 0));
+                    }
+                }
+            } else {
+                on_stack.insert(*beam, false);
+                stk.pop();
+            }
+        }
+
+        BeamTrace { visited, loops }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_beam_loop() {
+        let g = parse_char_grid("/.\\\n...\n\\./");
+        let mut tracer = BeamTracer::new(&g, OpticsTable::mirrors_and_splitters());
+
+        let t = tracer.trace(((0, 1), RIGHT));
+        assert_eq!(t.energized(), 8);
+        assert_eq!(t.loops.len(), 1);
+
+        let t = tracer.trace(((1, 1), RIGHT));
+        assert_eq!(t.energized(), 2);
+        assert!(t.loops.is_empty());
+    }
+
+    #[test]
+    fn test_custom_optics() {
+        // `#` absorbs beams
+        let g = parse_char_grid("..#..");
+        let mut optics = OpticsTable::default();
+        for d in FOUR_WAY {
+            optics.insert('#', d, vec![]);
+        }
+        let mut tracer = BeamTracer::new(&g, optics);
+        let t = tracer.trace(((0, 0), RIGHT));
+        assert_eq!(t.energized(), 3);
+        assert_eq!(t.visited.len(), 3);
+    }
+}
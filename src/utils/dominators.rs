@@ -0,0 +1,131 @@
+use petgraph::algo::dominators::simple_fast;
+
+use crate::prelude::*;
+
+/// The dominator tree of a support graph, as built by
+/// [`support_dominator_tree`]. Node `a` dominates node `b` if every chain of
+/// supports from the ground to `b` passes through `a`, i.e. `b` falls if `a`
+/// is removed.
+#[derive(Debug, Clone)]
+pub struct DominatorTree<K> {
+    /// Immediate dominator of each node; `None` if only the ground dominates
+    /// it.
+    pub idom: HashMap<K, Option<K>>,
+    pub children: HashMap<K, Vec<K>>,
+    /// Number of nodes dominated by each node, including itself.
+    pub subtree_size: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone> DominatorTree<K> {
+    /// Every node dominated by `k`, not including `k` itself.
+    pub fn dominated(&self, k: &K) -> Vec<K> {
+        let mut dominated = vec![];
+        let mut stk = self.children[k].clone();
+        while let Some(n) = stk.pop() {
+            stk.extend(self.children[&n].iter().cloned());
+            dominated.push(n);
+        }
+        dominated
+    }
+}
+
+/// Builds the dominator tree for a map from each node to the nodes it is
+/// supported by. Nodes which aren't supported by anything rest on the
+/// ground, which is the root of the tree.
+pub fn support_dominator_tree<K, I>(
+    supported_by: impl IntoIterator<Item = (K, I)>,
+) -> DominatorTree<K>
+where
+    K: Hash + Eq + Clone,
+    I: IntoIterator<Item = K>,
+{
+    let mut g = DiGraph::<Option<K>, ()>::new();
+    let ground = g.add_node(None);
+    let mut nodes = HashMap::default();
+
+    fn node<K: Hash + Eq + Clone>(
+        g: &mut DiGraph<Option<K>, ()>,
+        nodes: &mut HashMap<K, NodeIndex>,
+        k: K,
+    ) -> NodeIndex {
+        *nodes
+            .entry(k.clone())
+            .or_insert_with(|| g.add_node(Some(k)))
+    }
+
+    for (k, below) in supported_by {
+        let n = node(&mut g, &mut nodes, k);
+        for b in below {
+            let b = node(&mut g, &mut nodes, b);
+            g.add_edge(b, n, ());
+        }
+    }
+    for n in nodes.values() {
+        if g.neighbors_directed(*n, Incoming).next().is_none() {
+            g.add_edge(ground, *n, ());
+        }
+    }
+
+    let doms = simple_fast(&g, ground);
+    let key = |n: NodeIndex| g[n].clone();
+
+    let mut idom = HashMap::default();
+    let mut children: HashMap<K, Vec<K>> = nodes.keys().map(|k| (k.clone(), vec![])).collect();
+    let mut roots = vec![];
+    for (k, n) in &nodes {
+        let d = doms.immediate_dominator(*n).and_then(key);
+        match &d {
+            Some(d) => children.get_mut(d).unwrap().push(k.clone()),
+            None => roots.push(k.clone()),
+        }
+        idom.insert(k.clone(), d);
+    }
+
+    // Walk the tree top-down, then accumulate the sizes bottom-up.
+    let mut order = vec![];
+    let mut stk = roots;
+    while let Some(k) = stk.pop() {
+        stk.extend(children[&k].iter().cloned());
+        order.push(k);
+    }
+    let mut subtree_size: HashMap<K, usize> = HashMap::default();
+    for k in order.into_iter().rev() {
+        let size = 1 + children[&k].iter().map(|c| subtree_size[c]).sum::<usize>();
+        subtree_size.insert(k, size);
+    }
+
+    DominatorTree {
+        idom,
+        children,
+        subtree_size,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support_dominator_tree() {
+        // a holds up b and c, which both hold up d, which holds up e. f is on
+        // the ground by itself.
+        let tree = support_dominator_tree(vec![
+            ("b", vec!["a"]),
+            ("c", vec!["a"]),
+            ("d", vec!["b", "c"]),
+            ("e", vec!["d"]),
+            ("f", vec![]),
+        ]);
+
+        assert_eq!(tree.subtree_size["a"], 5);
+        assert_eq!(tree.subtree_size["b"], 1);
+        assert_eq!(tree.subtree_size["d"], 2);
+        assert_eq!(tree.subtree_size["f"], 1);
+        assert_eq!(tree.idom["d"], Some("a"));
+        assert_eq!(tree.idom["a"], None);
+
+        let mut dominated = tree.dominated(&"a");
+        dominated.sort();
+        assert_eq!(dominated, vec!["b", "c", "d", "e"]);
+    }
+}
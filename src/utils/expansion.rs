@@ -0,0 +1,82 @@
+use crate::prelude::*;
+
+/// Maps indices along one axis to coordinates after expansion, where every
+/// line in range that isn't occupied counts as `weight` lines instead of one.
+#[derive(Debug, Clone)]
+pub struct ExpansionMap<T> {
+    /// Sorted indices of the empty lines.
+    pub empty: Vec<i64>,
+    pub weight: T,
+}
+
+impl<T> ExpansionMap<T>
+where
+    T: Clone + From<i64> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    pub fn new(occupied: impl IntoIterator<Item = i64>, bounds: Range<i64>, weight: T) -> Self {
+        let occupied = occupied.into_iter().collect::<HashSet<_>>();
+        ExpansionMap {
+            empty: bounds.filter(|i| !occupied.contains(i)).collect(),
+            weight,
+        }
+    }
+
+    /// The expanded coordinate of `idx`.
+    pub fn get(&self, idx: i64) -> T {
+        let empty_before = self.empty.partition_point(|i| *i < idx) as i64;
+        T::from(idx) + T::from(empty_before) * (self.weight.clone() - T::from(1))
+    }
+}
+
+/// Sums |a - b| over every unordered pair of values in O(n log n).
+pub fn pairwise_distance_sum<T>(values: impl IntoIterator<Item = T>) -> T
+where
+    T: Clone + Ord + From<i64> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let mut values = values.into_iter().collect::<Vec<_>>();
+    values.sort();
+
+    // Each value is the larger of the pair for all the values before it.
+    let mut total = T::from(0);
+    let mut prefix = T::from(0);
+    for (idx, v) in values.into_iter().enumerate() {
+        total = total + v.clone() * T::from(idx as i64) - prefix.clone();
+        prefix = prefix + v;
+    }
+    total
+}
+
+/// Sums the Manhattan distance between every unordered pair of points.
+pub fn manhattan_distance_sum<T>(points: &[(T, T)]) -> T
+where
+    T: Clone + Ord + From<i64> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    pairwise_distance_sum(points.iter().map(|p| p.0.clone()))
+        + pairwise_distance_sum(points.iter().map(|p| p.1.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expansion_map() {
+        let m = ExpansionMap::new([0, 3], 0..5, 10_i64);
+        assert_eq!(m.empty, vec![1, 2, 4]);
+        assert_eq!(
+            (0..5).map(|i| m.get(i)).collect::<Vec<_>>(),
+            vec![0, 1, 11, 21, 22]
+        );
+
+        let weight = BigInt::from(10).pow(30);
+        let m = ExpansionMap::new([0, 3], 0..5, weight.clone());
+        assert_eq!(m.get(3), weight * 2 + 1);
+    }
+
+    #[test]
+    fn test_manhattan_distance_sum() {
+        assert_eq!(pairwise_distance_sum([3_i64, 1, 7]), 2 + 4 + 6);
+        let points: [(i64, i64); 3] = [(0, 0), (2, -1), (-3, 4)];
+        assert_eq!(manhattan_distance_sum(&points), 3 + 7 + 10);
+    }
+}
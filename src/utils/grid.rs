@@ -160,3 +160,461 @@ pub fn adjacents(
 ) -> impl Iterator<Item = (i64, i64)> {
     deltas.into_iter().map(move |(di, dj)| (i + di, j + dj))
 }
+
+/// A mirror line found by [`find_line_reflections`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineReflection {
+    /// Twice the index of the mirror line: odd values fall between two lines,
+    /// even values pass through the middle of a line.
+    pub doubled_pos: i64,
+    /// Every mismatched pair, as `(line_a, line_b, offset within the line)`.
+    pub mismatches: Vec<(usize, usize, usize)>,
+}
+
+/// Finds every mirror line across a sequence of lines (e.g. rows from
+/// `get_grid_row`) with at most `max_mismatches` differing elements. Mirror
+/// lines must have at least one line on either side of them.
+pub fn find_line_reflections<T: PartialEq>(
+    lines: &[Vec<T>],
+    max_mismatches: usize,
+) -> Vec<LineReflection> {
+    let n = lines.len() as i64;
+    let mut reflections = vec![];
+
+    for doubled_pos in 1..2 * n - 2 {
+        // The closest pair of lines straddling the mirror line
+        let (mut a, mut b) = (doubled_pos / 2, (doubled_pos + 1) / 2);
+        if a == b {
+            a -= 1;
+            b += 1;
+        }
+
+        let mut mismatches = vec![];
+        while a >= 0 && b < n && mismatches.len() <= max_mismatches {
+            let (a_u, b_u) = (a as usize, b as usize);
+            for (offset, (x, y)) in lines[a_u].iter().zip(lines[b_u].iter()).enumerate() {
+                if x != y {
+                    mismatches.push((a_u, b_u, offset));
+                }
+            }
+            a -= 1;
+            b += 1;
+        }
+
+        if mismatches.len() <= max_mismatches {
+            reflections.push(LineReflection {
+                doubled_pos,
+                mismatches,
+            });
+        }
+    }
+
+    reflections
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AxisOrientation {
+    /// A horizontal mirror line, which reflects rows onto each other.
+    Horizontal,
+    /// A vertical mirror line, which reflects columns onto each other.
+    Vertical,
+}
+
+/// A mirror line found by [`find_reflection_axes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectionAxis {
+    pub orientation: AxisOrientation,
+    /// Twice the row (or column) coordinate of the mirror line: odd values
+    /// fall between two rows, even values pass through the middle of one.
+    pub doubled_pos: i64,
+    /// Every pair of cells which differ across the mirror line.
+    pub mismatches: Vec<(Point, Point)>,
+}
+
+impl ReflectionAxis {
+    /// If the mirror line falls between two rows (or columns), returns the
+    /// index of the row (or column) immediately after it.
+    pub fn split_index(&self) -> Option<i64> {
+        if self.doubled_pos.rem_euclid(2) == 1 {
+            Some((self.doubled_pos + 1) / 2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Finds every horizontal and vertical mirror line in the grid with at most
+/// `max_mismatches` differing cells.
+pub fn find_reflection_axes(g: &IGrid2D, max_mismatches: usize) -> Vec<ReflectionAxis> {
+    let (i_bounds, j_bounds) = get_grid_bounds(g);
+
+    let rows = i_bounds
+        .clone()
+        .map(|i| get_grid_row(g, i).collect())
+        .collect::<Vec<Vec<_>>>();
+    let cols = j_bounds
+        .clone()
+        .map(|j| get_grid_col(g, j).collect())
+        .collect::<Vec<Vec<_>>>();
+
+    let horizontal = find_line_reflections(&rows, max_mismatches)
+        .into_iter()
+        .map(|r| ReflectionAxis {
+            orientation: AxisOrientation::Horizontal,
+            doubled_pos: r.doubled_pos + 2 * i_bounds.start,
+            mismatches: r
+                .mismatches
+                .into_iter()
+                .map(|(a, b, o)| {
+                    let j = j_bounds.start + o as i64;
+                    (
+                        (i_bounds.start + a as i64, j),
+                        (i_bounds.start + b as i64, j),
+                    )
+                })
+                .collect(),
+        });
+    let vertical = find_line_reflections(&cols, max_mismatches)
+        .into_iter()
+        .map(|r| ReflectionAxis {
+            orientation: AxisOrientation::Vertical,
+            doubled_pos: r.doubled_pos + 2 * j_bounds.start,
+            mismatches: r
+                .mismatches
+                .into_iter()
+                .map(|(a, b, o)| {
+                    let i = i_bounds.start + o as i64;
+                    (
+                        (i, j_bounds.start + a as i64),
+                        (i, j_bounds.start + b as i64),
+                    )
+                })
+                .collect(),
+        });
+
+    horizontal.chain(vertical).collect()
+}
+
+/// Returns every cell connected to `start` via `deltas` for which `include`
+/// is true, including `start` itself. Only cells in the grid are visited.
+pub fn flood_fill(
+    g: &IGrid2D,
+    start: Point,
+    deltas: &[Point],
+    include: impl Fn(Point, char) -> bool,
+) -> HashSet<Point> {
+    let mut visited = HashSet::default();
+    if !g.contains_key(&start) || !include(start, g[start]) {
+        return visited;
+    }
+    visited.insert(start);
+
+    let mut q = vec![start];
+    while let Some(p) = q.pop() {
+        for n in adjacents(p, deltas.iter().copied()) {
+            if !visited.contains(&n) && g.contains_key(&n) && include(n, g[n]) {
+                visited.insert(n);
+                q.push(n);
+            }
+        }
+    }
+
+    visited
+}
+
+/// A connected region found by [`label_regions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub label: usize,
+    pub cells: HashSet<Point>,
+    /// Number of cell edges between the region and cells outside of it.
+    pub perimeter: i64,
+    /// Number of straight fence segments needed to enclose the region (which
+    /// is the same as the number of corners).
+    pub sides: i64,
+    /// (min_i..max_i+1), (min_j..max_j+1), like `get_grid_bounds`
+    pub bounding_box: (Range<i64>, Range<i64>),
+    /// True if any cell of the region is on the edge of the grid.
+    pub touches_border: bool,
+}
+
+impl Region {
+    pub fn area(&self) -> i64 {
+        self.cells.len() as i64
+    }
+}
+
+/// Splits every cell for which `include` is true into connected regions
+/// using `deltas` (usually `FOUR_WAY` or `EIGHT_WAY`) as the adjacency.
+/// Returns a map from each included cell to the label of its region, and the
+/// regions themselves indexed by label.
+pub fn label_regions(
+    g: &IGrid2D,
+    deltas: &[Point],
+    include: impl Fn(Point, char) -> bool,
+) -> (HashMap<Point, usize>, Vec<Region>) {
+    label_regions_with(g, deltas, |_, p, c| include(p, c))
+}
+
+/// Like [`label_regions`], but every cell is included and a region is made of
+/// connected cells with the same character.
+pub fn label_char_regions(g: &IGrid2D, deltas: &[Point]) -> (HashMap<Point, usize>, Vec<Region>) {
+    label_regions_with(g, deltas, |seed, _, c| c == seed)
+}
+
+/// `include` is called with the character at the first cell of the region
+/// being filled, as well as the candidate cell.
+fn label_regions_with(
+    g: &IGrid2D,
+    deltas: &[Point],
+    include: impl Fn(char, Point, char) -> bool,
+) -> (HashMap<Point, usize>, Vec<Region>) {
+    let (i_bounds, j_bounds) = get_grid_bounds(g);
+    let mut labels = HashMap::default();
+    let mut regions = vec![];
+
+    for i in i_bounds.clone() {
+        for j in j_bounds.clone() {
+            if labels.contains_key(&(i, j)) {
+                continue;
+            }
+            let seed = g[(i, j)];
+            let cells = flood_fill(g, (i, j), deltas, |p, c| include(seed, p, c));
+            if cells.is_empty() {
+                continue;
+            }
+
+            let label = regions.len();
+            labels.extend(cells.iter().map(|p| (*p, label)));
+            regions.push(region_stats(label, cells, &i_bounds, &j_bounds));
+        }
+    }
+
+    (labels, regions)
+}
+
+fn region_stats(
+    label: usize,
+    cells: HashSet<Point>,
+    i_bounds: &Range<i64>,
+    j_bounds: &Range<i64>,
+) -> Region {
+    let mut perimeter = 0;
+    let mut sides = 0;
+    let (mut min, mut max) = ((i64::MAX, i64::MAX), (i64::MIN, i64::MIN));
+    let mut touches_border = false;
+
+    for p in &cells {
+        perimeter += adjacents(*p, FOUR_WAY)
+            .filter(|n| !cells.contains(n))
+            .count() as i64;
+
+        // Each corner of the region starts a new side; check each of the
+        // cell's four corners for either an outer or an inner corner.
+        for (d1, d2) in [(UP, LEFT), (UP, RIGHT), (DOWN, LEFT), (DOWN, RIGHT)] {
+            let a = cells.contains(&point_add(*p, d1));
+            let b = cells.contains(&point_add(*p, d2));
+            let diag = cells.contains(&point_add(*p, point_add(d1, d2)));
+            if (!a && !b) || (a && b && !diag) {
+                sides += 1;
+            }
+        }
+
+        min = (min.0.min(p.0), min.1.min(p.1));
+        max = (max.0.max(p.0), max.1.max(p.1));
+        touches_border |= p.0 == i_bounds.start
+            || p.0 == i_bounds.end - 1
+            || p.1 == j_bounds.start
+            || p.1 == j_bounds.end - 1;
+    }
+
+    Region {
+        label,
+        cells,
+        perimeter,
+        sides,
+        bounding_box: (min.0..max.0 + 1, min.1..max.1 + 1),
+        touches_border,
+    }
+}
+
+/// A horizontal run of cells, as found by [`find_spans`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub row: i64,
+    pub cols: Range<i64>,
+    pub value: String,
+}
+
+impl Span {
+    pub fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cols.clone().map(move |j| (self.row, j))
+    }
+
+    /// Cells which are `EIGHT_WAY` adjacent to any cell of the span, not
+    /// including the span itself.
+    pub fn border(&self) -> impl Iterator<Item = Point> + '_ {
+        (self.row - 1..=self.row + 1)
+            .flat_map(move |i| (self.cols.start - 1..=self.cols.end).map(move |j| (i, j)))
+            .filter(move |(i, j)| *i != self.row || !self.cols.contains(j))
+    }
+}
+
+/// Finds every maximal horizontal run of cells for which `include` is true,
+/// e.g. the numbers in a schematic.
+pub fn find_spans(g: &IGrid2D, include: impl Fn(char) -> bool) -> Vec<Span> {
+    let (i_bounds, j_bounds) = get_grid_bounds(g);
+    let mut spans = vec![];
+
+    for i in i_bounds {
+        let mut current: Option<Span> = None;
+        for j in j_bounds.clone() {
+            let ch = g[(i, j)];
+            match &mut current {
+                Some(span) if include(ch) => {
+                    span.cols.end = j + 1;
+                    span.value.push(ch);
+                }
+                None if include(ch) => {
+                    current = Some(Span {
+                        row: i,
+                        cols: j..j + 1,
+                        value: ch.to_string(),
+                    })
+                }
+                _ => spans.extend(current.take()),
+            }
+        }
+        spans.extend(current);
+    }
+
+    spans
+}
+
+/// Looks up which spans are next to a cell.
+#[derive(Debug, Clone)]
+pub struct SpanIndex {
+    pub spans: Vec<Span>,
+    bordering: HashMap<Point, Vec<usize>>,
+}
+
+impl SpanIndex {
+    pub fn new(spans: Vec<Span>) -> Self {
+        let mut bordering: HashMap<Point, Vec<usize>> = HashMap::default();
+        for (idx, span) in spans.iter().enumerate() {
+            for p in span.border() {
+                bordering.entry(p).or_default().push(idx);
+            }
+        }
+        SpanIndex { spans, bordering }
+    }
+
+    /// Spans which are `EIGHT_WAY` adjacent to `p`.
+    pub fn bordering(&self, p: Point) -> impl Iterator<Item = &Span> + '_ {
+        self.bordering
+            .get(&p)
+            .into_iter()
+            .flatten()
+            .map(|idx| &self.spans[*idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reflection_axes() {
+        let g = parse_char_grid("#.#\n..#\n#.#");
+        let axes = find_reflection_axes(&g, 0);
+        // Only the line through the middle row is a perfect reflection
+        assert_eq!(
+            axes,
+            vec![ReflectionAxis {
+                orientation: AxisOrientation::Horizontal,
+                doubled_pos: 2,
+                mismatches: vec![],
+            }]
+        );
+        assert_eq!(axes[0].split_index(), None);
+
+        let axes = find_reflection_axes(&g, 1);
+        let vertical = axes
+            .iter()
+            .filter(|a| a.orientation == AxisOrientation::Vertical)
+            .collect::<Vec<_>>();
+        assert_eq!(vertical.len(), 1);
+        assert_eq!(vertical[0].doubled_pos, 2);
+        assert_eq!(vertical[0].mismatches, vec![((1, 0), (1, 2))]);
+    }
+
+    #[test]
+    fn test_label_regions() {
+        let g = parse_char_grid("AAAA\nBBCD\nBBCC\nEEEC");
+        let (labels, regions) = label_char_regions(&g, &FOUR_WAY);
+        assert_eq!(regions.len(), 5);
+
+        let stats = |p: Point| {
+            let r = &regions[labels[&p]];
+            (r.area(), r.perimeter, r.sides)
+        };
+        assert_eq!(stats((0, 0)), (4, 10, 4));
+        assert_eq!(stats((1, 0)), (4, 8, 4));
+        assert_eq!(stats((1, 2)), (4, 10, 8));
+        assert_eq!(stats((1, 3)), (1, 4, 4));
+        assert_eq!(stats((3, 0)), (3, 8, 4));
+
+        let d = &regions[labels[&(1, 3)]];
+        assert_eq!(d.bounding_box, (1..2, 3..4));
+        assert!(d.touches_border);
+    }
+
+    #[test]
+    fn test_label_regions_enclosed() {
+        let g = parse_char_grid("#####\n#...#\n#.#.#\n#...#\n####.");
+        let (_, regions) = label_regions(&g, &FOUR_WAY, |_, c| c == '.');
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].area(), 8);
+        assert_eq!(regions[0].perimeter, 16);
+        assert_eq!(regions[0].sides, 8);
+        assert!(!regions[0].touches_border);
+        assert!(regions[1].touches_border);
+
+        let (_, regions) = label_regions(&g, &EIGHT_WAY, |_, c| c == '.');
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].area(), 9);
+    }
+
+    #[test]
+    fn test_spans() {
+        let g = parse_char_grid("12.x\n.*34");
+        let spans = find_spans(&g, |c| c.is_ascii_digit());
+        assert_eq!(
+            spans,
+            vec![
+                Span {
+                    row: 0,
+                    cols: 0..2,
+                    value: "12".to_string(),
+                },
+                Span {
+                    row: 1,
+                    cols: 2..4,
+                    value: "34".to_string(),
+                },
+            ]
+        );
+        assert_eq!(spans[1].border().count(), 10);
+        assert!(spans[1].border().any(|p| g[p] == 'x'));
+
+        let index = SpanIndex::new(spans);
+        assert_eq!(index.bordering((1, 1)).count(), 2);
+        assert_eq!(
+            index
+                .bordering((0, 3))
+                .map(|s| &s.value)
+                .collect::<Vec<_>>(),
+            vec!["34"]
+        );
+    }
+}
@@ -1,4 +1,11 @@
-use std::{fmt::Debug, ops::RangeInclusive};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    ops::{
+        Bound::{Excluded, Unbounded},
+        RangeInclusive,
+    },
+};
 
 /// Converts from (base, len) to base..=base+len
 pub fn interval_base_and_len_to_range((base, len): (i64, i64)) -> RangeInclusive<i64> {
@@ -151,6 +158,202 @@ pub fn collapse_adjacent_intervals<T: Eq>(
     ret
 }
 
+/// A map on i64 which adds a constant offset to each of a set of ranges,
+/// e.g. the almanac maps of 2023 day 5. Points outside every range map to
+/// themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseOffsetMap {
+    /// The offset which applies from each key up to the next key. Always
+    /// contains `i64::MIN`, and adjacent entries have different offsets.
+    breaks: BTreeMap<i64, i64>,
+}
+
+impl Default for PiecewiseOffsetMap {
+    fn default() -> Self {
+        PiecewiseOffsetMap::identity()
+    }
+}
+
+impl PiecewiseOffsetMap {
+    pub fn identity() -> Self {
+        PiecewiseOffsetMap {
+            breaks: BTreeMap::from([(i64::MIN, 0)]),
+        }
+    }
+
+    /// Builds a map from `(dest_start, source_start, len)` triples. Where
+    /// ranges overlap the first one to match wins, as when applying the lines
+    /// of an almanac map in order.
+    pub fn from_ranges(ranges: impl IntoIterator<Item = (i64, i64, i64)>) -> Self {
+        let ranges = ranges.into_iter().collect::<Vec<_>>();
+        let mut m = PiecewiseOffsetMap::identity();
+        // Inserting in reverse lets earlier ranges overwrite later ones.
+        for (dest_start, source_start, len) in ranges.into_iter().rev() {
+            if len > 0 {
+                m.insert(
+                    source_start..=source_start + len - 1,
+                    dest_start - source_start,
+                );
+            }
+        }
+        m
+    }
+
+    fn offset_at(&self, x: i64) -> i64 {
+        *self.breaks.range(..=x).next_back().unwrap().1
+    }
+
+    /// Maps every point in `source` to itself plus `offset`.
+    pub fn insert(&mut self, source: RangeInclusive<i64>, offset: i64) {
+        if source.is_empty() {
+            return;
+        }
+        let (start, end) = source.into_inner();
+        if end < i64::MAX {
+            let after = self.offset_at(end + 1);
+            self.breaks.insert(end + 1, after);
+        }
+        let inside = self
+            .breaks
+            .range(start..=end)
+            .map(|(k, _)| *k)
+            .collect::<Vec<_>>();
+        for k in inside {
+            self.breaks.remove(&k);
+        }
+        self.breaks.insert(start, offset);
+        self.normalize();
+    }
+
+    fn normalize(&mut self) {
+        let mut prev = None;
+        self.breaks.retain(|_, offset| {
+            let keep = prev != Some(*offset);
+            prev = Some(*offset);
+            keep
+        });
+    }
+
+    /// Every maximal range of the domain with a constant offset, in order.
+    pub fn segments(&self) -> impl Iterator<Item = (RangeInclusive<i64>, i64)> + '_ {
+        let ends = self.breaks.keys().skip(1).map(|k| k - 1).chain([i64::MAX]);
+        self.breaks
+            .iter()
+            .zip(ends)
+            .map(|((start, offset), end)| (*start..=end, *offset))
+    }
+
+    /// The segments which overlap `r`, clipped to it.
+    fn segments_within(
+        &self,
+        r: RangeInclusive<i64>,
+    ) -> impl Iterator<Item = (RangeInclusive<i64>, i64)> + '_ {
+        let (lo, hi) = r.into_inner();
+        let first = *self.breaks.range(..=lo).next_back().unwrap().0;
+        self.breaks.range(first..=hi).map(move |(start, offset)| {
+            let end = self
+                .breaks
+                .range((Excluded(*start), Unbounded))
+                .next()
+                .map_or(i64::MAX, |(next, _)| next - 1);
+            (*start.max(&lo)..=end.min(hi), *offset)
+        })
+    }
+
+    pub fn apply(&self, x: i64) -> i64 {
+        x + self.offset_at(x)
+    }
+
+    /// The image of `r`, as one range per segment it overlaps.
+    pub fn apply_interval(&self, r: RangeInclusive<i64>) -> Vec<RangeInclusive<i64>> {
+        if r.is_empty() {
+            return vec![];
+        }
+        self.segments_within(r)
+            .map(|(seg, offset)| seg.start() + offset..=seg.end() + offset)
+            .collect()
+    }
+
+    /// The image of a set of ranges, sorted with overlapping and adjacent
+    /// ranges merged.
+    pub fn apply_intervals(
+        &self,
+        intervals: impl IntoIterator<Item = RangeInclusive<i64>>,
+    ) -> Vec<RangeInclusive<i64>> {
+        let mut image = intervals
+            .into_iter()
+            .flat_map(|r| self.apply_interval(r))
+            .collect::<Vec<_>>();
+        image.sort_by_key(|r| *r.start());
+
+        let mut merged: Vec<RangeInclusive<i64>> = vec![];
+        for r in image {
+            match merged.last_mut() {
+                Some(prev) if *r.start() <= prev.end().saturating_add(1) => {
+                    *prev = *prev.start()..=*prev.end().max(r.end());
+                }
+                _ => merged.push(r),
+            }
+        }
+        merged
+    }
+
+    /// The map which applies `self` and then `then`.
+    pub fn compose(&self, then: &PiecewiseOffsetMap) -> PiecewiseOffsetMap {
+        let mut breaks = BTreeMap::new();
+        for (seg, offset) in self.segments() {
+            // Clip the image to i64 so that it can be looked up in `then`.
+            let image_lo = (*seg.start() as i128 + offset as i128).max(i64::MIN as i128) as i64;
+            let image_hi = (*seg.end() as i128 + offset as i128).min(i64::MAX as i128) as i64;
+            if image_lo > image_hi {
+                continue;
+            }
+            for (image, then_offset) in then.segments_within(image_lo..=image_hi) {
+                breaks.insert(image.start() - offset, offset + then_offset);
+            }
+        }
+        breaks.entry(i64::MIN).or_insert(0);
+
+        let mut m = PiecewiseOffsetMap { breaks };
+        m.normalize();
+        m
+    }
+
+    /// The inverse map, if this map is a bijection.
+    pub fn invert(&self) -> Option<PiecewiseOffsetMap> {
+        let mut images = self
+            .segments()
+            .map(|(seg, offset)| {
+                let lo = *seg.start() as i128 + offset as i128;
+                let hi = *seg.end() as i128 + offset as i128;
+                (lo, hi, offset)
+            })
+            .collect::<Vec<_>>();
+        images.sort();
+
+        // The images have to tile i64 exactly.
+        let mut next = i64::MIN as i128;
+        for (lo, hi, _) in &images {
+            if *lo != next {
+                return None;
+            }
+            next = hi + 1;
+        }
+        if next != i64::MAX as i128 + 1 {
+            return None;
+        }
+
+        let mut m = PiecewiseOffsetMap {
+            breaks: images
+                .into_iter()
+                .map(|(lo, _, offset)| (lo as i64, -offset))
+                .collect(),
+        };
+        m.normalize();
+        Some(m)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +408,52 @@ mod tests {
         assert_eq!(split_interval_at(0..=0, 0), ((0..=0), (0..=0)));
         assert_eq!(split_interval_at(0..=1, 0), ((0..=0), (1..=1)));
     }
+
+    #[test]
+    fn test_piecewise_offset_map() {
+        let seed_to_soil = PiecewiseOffsetMap::from_ranges([(50, 98, 2), (52, 50, 48)]);
+        assert_eq!(seed_to_soil.apply(79), 81);
+        assert_eq!(seed_to_soil.apply(99), 51);
+        assert_eq!(seed_to_soil.apply(10), 10);
+        assert_eq!(
+            seed_to_soil.segments().collect::<Vec<_>>(),
+            vec![
+                (i64::MIN..=49, 0),
+                (50..=97, 2),
+                (98..=99, -48),
+                (100..=i64::MAX, 0)
+            ]
+        );
+        assert_eq!(
+            seed_to_soil.apply_interval(90..=100),
+            vec![92..=99, 50..=51, 100..=100]
+        );
+        assert_eq!(
+            seed_to_soil.apply_intervals([90..=100, 40..=49]),
+            vec![40..=51, 92..=100]
+        );
+
+        let soil_to_fertilizer =
+            PiecewiseOffsetMap::from_ranges([(0, 15, 37), (37, 52, 2), (39, 0, 15)]);
+        let composed = seed_to_soil.compose(&soil_to_fertilizer);
+        for x in [0, 13, 14, 49, 50, 55, 79, 97, 98, 99, 100] {
+            assert_eq!(
+                composed.apply(x),
+                soil_to_fertilizer.apply(seed_to_soil.apply(x))
+            );
+        }
+
+        let inverse = composed.invert().unwrap();
+        for x in -5..120 {
+            assert_eq!(inverse.apply(composed.apply(x)), x);
+        }
+        assert_eq!(composed.compose(&inverse), PiecewiseOffsetMap::identity());
+
+        let overlapping = PiecewiseOffsetMap::from_ranges([(100, 0, 10), (200, 5, 10)]);
+        assert_eq!(overlapping.apply(7), 107);
+        assert_eq!(overlapping.apply(12), 207);
+
+        let not_injective = PiecewiseOffsetMap::from_ranges([(0, 10, 5)]);
+        assert_eq!(not_injective.invert(), None);
+    }
 }
@@ -0,0 +1,128 @@
+use crate::prelude::*;
+
+/// A grid maze reduced to the cells where a path could branch, as built by
+/// [`grid_to_junction_graph`].
+#[derive(Debug, Clone)]
+pub struct JunctionGraph {
+    /// Nodes are junction positions, edges are the length of the corridor
+    /// between them.
+    pub graph: Graph<Point, i64>,
+    pub positions: HashMap<Point, NodeIndex>,
+    pub start: NodeIndex,
+    pub end: NodeIndex,
+}
+
+/// Slope tiles can only be left in the direction they point.
+fn allowed_moves(c: char) -> &'static [Point] {
+    match c {
+        '>' => &[RIGHT],
+        '<' => &[LEFT],
+        '^' => &[UP],
+        'v' => &[DOWN],
+        _ => &FOUR_WAY,
+    }
+}
+
+/// Simplify the grid by following the hallways and making them into long
+/// edges. Junctions are the passable cells in the grid which don't have
+/// exactly two passable neighbors, as well as `start` and `end`.
+///
+/// Slope tiles (`>`, `<`, `^`, `v`) are one-way, so a corridor is only
+/// turned into an edge in the directions which can actually be walked.
+pub fn grid_to_junction_graph(
+    g: &IGrid2D,
+    passable: impl Fn(char) -> bool,
+    start: Point,
+    end: Point,
+) -> JunctionGraph {
+    let (i_bounds, j_bounds) = get_grid_bounds(g);
+    let mut graph = Graph::new();
+    let mut positions = HashMap::default();
+    let open = |p: &Point| g.contains_key(p) && passable(g[p]);
+
+    for i in i_bounds {
+        for j in j_bounds.clone() {
+            let p = (i, j);
+            if open(&p) {
+                let exits = adjacents(p, FOUR_WAY).filter(open).count();
+                if exits != 2 || p == start || p == end {
+                    positions.insert(p, graph.add_node(p));
+                }
+            }
+        }
+    }
+
+    for (&p, &from) in &positions {
+        for first in adjacents(p, allowed_moves(g[p]).iter().copied()) {
+            if !open(&first) {
+                continue;
+            }
+
+            let (mut prev, mut curr, mut len) = (p, first, 1);
+            loop {
+                if let Some(&to) = positions.get(&curr) {
+                    graph.add_edge(from, to, len);
+                    break;
+                }
+                // Corridor cells only have one way forward, unless a slope
+                // blocks it.
+                match adjacents(curr, allowed_moves(g[curr]).iter().copied())
+                    .find(|n| *n != prev && open(n))
+                {
+                    Some(next) => {
+                        prev = curr;
+                        curr = next;
+                        len += 1;
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    JunctionGraph {
+        start: positions[&start],
+        end: positions[&end],
+        graph,
+        positions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_junction_graph() {
+        let g = parse_char_grid("#.###\n#...#\n#.#.#\n#...#\n###.#");
+        let jg = grid_to_junction_graph(&g, |c| c == '.', (0, 1), (4, 3));
+        // start, end, and the two T-junctions
+        assert_eq!(jg.graph.node_count(), 4);
+        assert_eq!(jg.graph.edge_count(), 8);
+
+        let a = jg.positions[&(1, 1)];
+        let b = jg.positions[&(3, 3)];
+        let mut lens = jg
+            .graph
+            .edges_connecting(a, b)
+            .map(|e| *e.weight())
+            .collect::<Vec<_>>();
+        lens.sort();
+        assert_eq!(lens, vec![4, 4]);
+    }
+
+    #[test]
+    fn test_junction_graph_slopes() {
+        let g = parse_char_grid("#.###\n#.>.#\n###.#");
+        let jg = grid_to_junction_graph(&g, |c| c != '#', (0, 1), (2, 3));
+        assert_eq!(jg.graph.node_count(), 2);
+        assert_eq!(
+            jg.graph
+                .edges_connecting(jg.start, jg.end)
+                .map(|e| *e.weight())
+                .collect::<Vec<_>>(),
+            vec![4]
+        );
+        assert_eq!(jg.graph.edges_connecting(jg.end, jg.start).count(), 0);
+    }
+}
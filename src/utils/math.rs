@@ -25,6 +25,101 @@ pub fn differences(x: &[i64]) -> Vec<i64> {
     x.windows(2).map(|w| w[1] - w[0]).collect()
 }
 
+/// The leading diagonal of the finite difference table of a sequence, which
+/// determines the lowest degree polynomial through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DifferenceTable {
+    /// Δᵏy(0) for each k up to the degree of the polynomial.
+    pub leading: Vec<BigInt>,
+    /// Number of values the table was built from.
+    pub len: usize,
+}
+
+impl DifferenceTable {
+    /// Builds the table in i64, redoing it with `BigInt` if that overflows.
+    /// Stops at the first row of all zeros, so the degree is minimal.
+    pub fn new(y: &[i64]) -> Self {
+        let leading = match Self::leading_checked(y.to_vec()) {
+            Some(leading) => leading.into_iter().map(BigInt::from).collect(),
+            None => Self::leading_big(y.iter().map(|v| BigInt::from(*v)).collect()),
+        };
+        DifferenceTable {
+            leading,
+            len: y.len(),
+        }
+    }
+
+    fn leading_checked(mut row: Vec<i64>) -> Option<Vec<i64>> {
+        let mut leading = vec![];
+        while row.iter().any(|v| *v != 0) {
+            leading.push(row[0]);
+            row = row
+                .windows(2)
+                .map(|w| w[1].checked_sub(w[0]))
+                .collect::<Option<_>>()?;
+        }
+        Some(leading)
+    }
+
+    fn leading_big(mut row: Vec<BigInt>) -> Vec<BigInt> {
+        let mut leading = vec![];
+        while row.iter().any(|v| *v != BigInt::from(0)) {
+            leading.push(row[0].clone());
+            row = row.windows(2).map(|w| &w[1] - &w[0]).collect();
+        }
+        leading
+    }
+
+    /// Degree of the fitted polynomial; a sequence of zeros has degree 0.
+    pub fn degree(&self) -> usize {
+        self.leading.len().saturating_sub(1)
+    }
+
+    /// The value at index `x` of the original sequence, where `x` may be
+    /// negative or past the end.
+    pub fn at(&self, x: i64) -> BigInt {
+        match self.at_checked(x) {
+            Some(v) => v.into(),
+            None => self.at_big(x),
+        }
+    }
+
+    /// Newton's forward formula: y(x) = Σ C(x, k) Δᵏy(0). The binomials are
+    /// updated with C(x, k + 1) = C(x, k) (x - k) / (k + 1), which is exact
+    /// for any integer x.
+    fn at_checked(&self, x: i64) -> Option<i128> {
+        use ::num::ToPrimitive;
+
+        let mut binom = 1_i128;
+        let mut total = 0_i128;
+        for (k, d) in self.leading.iter().enumerate() {
+            total = total.checked_add(binom.checked_mul(d.to_i128()?)?)?;
+            binom = binom.checked_mul(x as i128 - k as i128)? / (k as i128 + 1);
+        }
+        Some(total)
+    }
+
+    fn at_big(&self, x: i64) -> BigInt {
+        let mut binom = BigInt::from(1);
+        let mut total = BigInt::from(0);
+        for (k, d) in self.leading.iter().enumerate() {
+            total += &binom * d;
+            binom = binom * (x - k as i64) / (k as i64 + 1);
+        }
+        total
+    }
+
+    /// The value `steps` past the last one in the sequence.
+    pub fn forward(&self, steps: i64) -> BigInt {
+        self.at(self.len as i64 - 1 + steps)
+    }
+
+    /// The value `steps` before the first one in the sequence.
+    pub fn backward(&self, steps: i64) -> BigInt {
+        self.at(-steps)
+    }
+}
+
 pub fn polynomial_regression(x: &[i64], y: &[i64], n: usize) -> NewtonPolynomial {
     assert!(x.len() > n);
     assert_eq!(x.len(), y.len());
@@ -98,6 +193,276 @@ pub fn lcm(u: i64, v: i64) -> i64 {
     u * (v / gcd(u, v))
 }
 
+/// Returns (g, x, y) such that a*x + b*y = g = gcd(a, b).
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a.rem_euclid(b));
+        (g, y, x - a.div_euclid(b) * y)
+    }
+}
+
+/// Chinese remainder theorem: finds x such that x = a1 (mod n1) and x = a2
+/// (mod n2). Returns (x, lcm(n1, n2)) with 0 <= x < lcm(n1, n2), or `None`
+/// if there is no solution. The moduli don't need to be coprime.
+pub fn crt((a1, n1): (i64, i64), (a2, n2): (i64, i64)) -> Option<(i64, i64)> {
+    let (g, p, _) = extended_gcd(n1, n2);
+    if (a2 - a1).rem_euclid(g) != 0 {
+        return None;
+    }
+    let l = n1 / g * n2;
+    let k = ((a2 - a1) / g) as i128 * p as i128 % (n2 / g) as i128;
+    let x = (a1 as i128 + n1 as i128 * k).rem_euclid(l as i128);
+    Some((x as i64, l))
+}
+
+/// Returns the integers x for which a*x² + b*x + c > 0 (or >= 0 when
+/// `strict` is false), or `None` if there are none. The parabola must open
+/// downwards (a < 0) so that the solutions form a single interval.
+///
+/// Everything is computed exactly using an integer square root, so roots
+/// which land on integers are handled correctly.
+pub fn quadratic_interval_big(
+    a: &BigInt,
+    b: &BigInt,
+    c: &BigInt,
+    strict: bool,
+) -> Option<RangeInclusive<BigInt>> {
+    use ::num::{Integer, Signed};
+
+    assert!(a.is_negative(), "expected a < 0, got {}", a);
+    let holds = |x: &BigInt| {
+        let y = a * x * x + b * x + c;
+        if strict {
+            y.is_positive()
+        } else {
+            !y.is_negative()
+        }
+    };
+
+    let discriminant = b * b - BigInt::from(4) * a * c;
+    if discriminant.is_negative() {
+        return None;
+    }
+
+    // With A = -a the roots are (b ± √D) / 2A. The floored square root puts
+    // these estimates within a step or two of the exact bounds.
+    let s = discriminant.sqrt();
+    let two_a = BigInt::from(-2) * a;
+    let mut lo = (b - &s).div_floor(&two_a) - 1;
+    let mut hi = (b + &s).div_floor(&two_a) + 2;
+
+    while lo <= hi && !holds(&lo) {
+        lo += 1;
+    }
+    while hi >= lo && !holds(&hi) {
+        hi -= 1;
+    }
+
+    (lo <= hi).then_some(lo..=hi)
+}
+
+/// As [`quadratic_interval_big`], for coefficients which fit in an `i128`.
+pub fn quadratic_interval(a: i128, b: i128, c: i128, strict: bool) -> Option<RangeInclusive<i128>> {
+    let range = quadratic_interval_big(&a.into(), &b.into(), &c.into(), strict)?;
+    let (lo, hi) = range.into_inner();
+    Some(lo.try_into().expect("root out of range")..=hi.try_into().expect("root out of range"))
+}
+
+/// An integer modulo the prime `P`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModInt<const P: u64>(pub u64);
+
+impl<const P: u64> ModInt<P> {
+    pub fn new(v: i64) -> Self {
+        ModInt(v.rem_euclid(P as i64) as u64)
+    }
+
+    pub fn pow(self, mut e: u64) -> Self {
+        let mut base = self;
+        let mut acc = ModInt(1 % P);
+        while e > 0 {
+            if e & 1 == 1 {
+                acc = acc * base;
+            }
+            base = base * base;
+            e >>= 1;
+        }
+        acc
+    }
+
+    /// Multiplicative inverse by Fermat's little theorem.
+    pub fn inv(self) -> Self {
+        assert!(self.0 != 0, "division by zero mod {}", P);
+        self.pow(P - 2)
+    }
+}
+
+impl<const P: u64> Add for ModInt<P> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        ModInt(((self.0 as u128 + rhs.0 as u128) % P as u128) as u64)
+    }
+}
+
+impl<const P: u64> Sub for ModInt<P> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        ModInt(((self.0 as u128 + P as u128 - rhs.0 as u128) % P as u128) as u64)
+    }
+}
+
+impl<const P: u64> Mul for ModInt<P> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        ModInt(((self.0 as u128 * rhs.0 as u128) % P as u128) as u64)
+    }
+}
+
+impl<const P: u64> Div for ModInt<P> {
+    type Output = Self;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: Self) -> Self {
+        self * rhs.inv()
+    }
+}
+
+impl<const P: u64> ::num::Zero for ModInt<P> {
+    fn zero() -> Self {
+        ModInt(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl<const P: u64> ::num::One for ModInt<P> {
+    fn one() -> Self {
+        ModInt(1 % P)
+    }
+}
+
+/// A linear recurrence a(n) = c₁·a(n-1) + ... + c_d·a(n-d), along with its
+/// first d values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinearRecurrence<T> {
+    pub coefficients: Vec<T>,
+    pub initial: Vec<T>,
+}
+
+/// Finds the shortest linear recurrence which generates `seq`, over a field
+/// such as `BigRational` or `ModInt`. A recurrence of order d is only
+/// determined uniquely by at least 2d values, so pass more values than the
+/// order you expect.
+pub fn berlekamp_massey<T>(seq: &[T]) -> LinearRecurrence<T>
+where
+    T: Clone + PartialEq + ::num::Zero + ::num::One + Sub<Output = T> + Div<Output = T>,
+{
+    // Connection polynomials: c is the current one, b the one before the
+    // last length change, which was off by `b_discrepancy`.
+    let mut c = vec![T::one()];
+    let mut b = vec![T::one()];
+    let mut b_discrepancy = T::one();
+    let mut len = 0;
+    let mut shift = 1;
+
+    for n in 0..seq.len() {
+        let discrepancy = (1..=len).fold(seq[n].clone(), |acc, i| {
+            acc + c[i].clone() * seq[n - i].clone()
+        });
+        if discrepancy.is_zero() {
+            shift += 1;
+            continue;
+        }
+
+        let scale = discrepancy.clone() / b_discrepancy.clone();
+        let previous = c.clone();
+        if c.len() < b.len() + shift {
+            c.resize(b.len() + shift, T::zero());
+        }
+        for (i, bi) in b.iter().enumerate() {
+            c[i + shift] = c[i + shift].clone() - scale.clone() * bi.clone();
+        }
+
+        if 2 * len <= n {
+            len = n + 1 - len;
+            b = previous;
+            b_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            shift += 1;
+        }
+    }
+
+    c.resize(len + 1, T::zero());
+    LinearRecurrence {
+        coefficients: c[1..].iter().map(|ci| T::zero() - ci.clone()).collect(),
+        initial: seq[..len].to_vec(),
+    }
+}
+
+impl<T> LinearRecurrence<T>
+where
+    T: Clone + ::num::Zero + ::num::One + Sub<Output = T>,
+{
+    /// Multiplies two polynomials of degree < d modulo the characteristic
+    /// polynomial x^d - c₁x^(d-1) - ... - c_d.
+    fn mul_mod(&self, a: &[T], b: &[T]) -> Vec<T> {
+        let d = self.coefficients.len();
+        let mut product = vec![T::zero(); 2 * d - 1];
+        for (i, ai) in a.iter().enumerate() {
+            for (j, bj) in b.iter().enumerate() {
+                product[i + j] = product[i + j].clone() + ai.clone() * bj.clone();
+            }
+        }
+        // Reduce from the top using x^d = Σ cᵢ x^(d-i).
+        for k in (d..product.len()).rev() {
+            let top = std::mem::replace(&mut product[k], T::zero());
+            for (i, ci) in self.coefficients.iter().enumerate() {
+                product[k - 1 - i] = product[k - 1 - i].clone() + top.clone() * ci.clone();
+            }
+        }
+        product.truncate(d);
+        product
+    }
+
+    /// The n-th term of the sequence (0-indexed), computed by raising x to
+    /// the n-th power modulo the characteristic polynomial.
+    pub fn nth(&self, mut n: u64) -> T {
+        let d = self.coefficients.len();
+        if d == 0 {
+            return T::zero();
+        }
+        if n < d as u64 {
+            return self.initial[n as usize].clone();
+        }
+
+        let mut result = vec![T::zero(); d];
+        result[0] = T::one();
+        let mut base = vec![T::zero(); d];
+        if d == 1 {
+            base[0] = self.coefficients[0].clone();
+        } else {
+            base[1] = T::one();
+        }
+
+        while n > 0 {
+            if n & 1 == 1 {
+                result = self.mul_mod(&result, &base);
+            }
+            base = self.mul_mod(&base, &base);
+            n >>= 1;
+        }
+
+        result
+            .into_iter()
+            .zip(self.initial.iter())
+            .fold(T::zero(), |acc, (r, a)| acc + r * a.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,4 +495,97 @@ mod tests {
         let y = vec![1, 8, 1000, 8000];
         polynomial_regression(&x, &y, 3);
     }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(crt((2, 3), (3, 5)), Some((8, 15)));
+        assert_eq!(crt((0, 3739), (0, 3761)), Some((0, 3739 * 3761)));
+        assert_eq!(crt((1, 4), (3, 6)), Some((9, 12)));
+        assert_eq!(crt((1, 4), (2, 6)), None);
+    }
+
+    #[test]
+    fn test_quadratic_interval() {
+        // -x² + 7x - 9 > 0
+        assert_eq!(quadratic_interval(-1, 7, -9, true), Some(2..=5));
+        // -x² + 30x - 200 has roots at exactly 10 and 20.
+        assert_eq!(quadratic_interval(-1, 30, -200, true), Some(11..=19));
+        assert_eq!(quadratic_interval(-1, 30, -200, false), Some(10..=20));
+        // -x² + 4x - 4 touches zero only at x = 2.
+        assert_eq!(quadratic_interval(-1, 4, -4, true), None);
+        assert_eq!(quadratic_interval(-1, 4, -4, false), Some(2..=2));
+        assert_eq!(quadratic_interval(-3, 0, -1, false), None);
+
+        let t = 1_000_000_000_000_i128;
+        let range = quadratic_interval(-1, t, -(t / 2) * (t / 2), false);
+        assert_eq!(range, Some(t / 2..=t / 2));
+    }
+
+    #[test]
+    fn test_difference_table() {
+        let t = DifferenceTable::new(&[10, 13, 16, 21, 30, 45]);
+        assert_eq!(t.degree(), 3);
+        assert_eq!(t.forward(1), BigInt::from(68));
+        assert_eq!(t.backward(1), BigInt::from(5));
+        assert_eq!(t.at(2), BigInt::from(16));
+
+        let squares = DifferenceTable::new(&[0, 1, 4, 9, 16, 25, 36]);
+        assert_eq!(squares.degree(), 2);
+        assert_eq!(squares.backward(1000), BigInt::from(1_000_000));
+        assert_eq!(
+            squares.forward(10_i64.pow(12) - 6),
+            BigInt::from(10).pow(24)
+        );
+
+        let constant = DifferenceTable::new(&[7, 7, 7]);
+        assert_eq!(
+            (constant.degree(), constant.forward(5)),
+            (0, BigInt::from(7))
+        );
+
+        // The differences overflow i64, so the table is built with BigInt.
+        let big = DifferenceTable::new(&[i64::MIN, i64::MAX, i64::MIN]);
+        assert_eq!(big.degree(), 2);
+        assert_eq!(
+            big.forward(1),
+            BigInt::from(i64::MIN) - BigInt::from(u64::MAX) * 3
+        );
+    }
+
+    #[test]
+    fn test_berlekamp_massey() {
+        let fib = [0, 1, 1, 2, 3, 5, 8, 13].map(|v| BigRational::from_integer(BigInt::from(v)));
+        let r = berlekamp_massey(&fib);
+        assert_eq!(r.coefficients, vec![BigRational::from_integer(1.into()); 2]);
+        assert_eq!(
+            r.nth(90),
+            BigRational::from_integer(2880067194370816120_i64.into())
+        );
+
+        // n² + n/2 needs rationals, and satisfies the recurrence of (x - 1)³.
+        let quadratic = (0..6)
+            .map(|n| BigRational::new((2 * n * n + n).into(), 2.into()))
+            .collect::<Vec<_>>();
+        let r = berlekamp_massey(&quadratic);
+        assert_eq!(r.coefficients.len(), 3);
+        assert_eq!(
+            r.nth(1001),
+            BigRational::new((2 * 1001 * 1001 + 1001).into(), 2.into())
+        );
+
+        type M = ModInt<1_000_000_007>;
+        let powers = (0..10)
+            .map(|n| M::new(3).pow(n) + M::new(n as i64))
+            .collect::<Vec<_>>();
+        let r = berlekamp_massey(&powers);
+        assert_eq!(r.coefficients.len(), 3);
+        let n = 10_u64.pow(18);
+        assert_eq!(
+            r.nth(n),
+            M::new(3).pow(n) + M::new((n % 1_000_000_007) as i64)
+        );
+
+        assert_eq!(berlekamp_massey(&[M::new(0); 4]).nth(100), M::new(0));
+    }
 }
@@ -1,9 +1,15 @@
 mod parser;
 pub use parser::*;
 
+mod text;
+pub use text::*;
+
 mod grid;
 pub use grid::*;
 
+mod tiled_grid;
+pub use tiled_grid::*;
+
 mod intervals;
 pub use intervals::*;
 
@@ -15,11 +21,29 @@ pub use math::*;
 pub mod defaultmap;
 pub use defaultmap::*;
 
+pub mod expansion;
+pub use expansion::*;
+
 pub mod cycles;
 pub use cycles::*;
 
+pub mod beam;
+pub use beam::*;
+
 pub mod lattice_polygons;
 pub use lattice_polygons::*;
 
 pub mod flow;
 pub use flow::*;
+
+pub mod junction_graph;
+pub use junction_graph::*;
+
+pub mod search;
+pub use search::*;
+
+pub mod dominators;
+pub use dominators::*;
+
+pub mod physics;
+pub use physics::*;
@@ -1,3 +1,9 @@
+use std::iter::{Enumerate, Peekable};
+use std::str::Lines;
+use std::sync::OnceLock;
+
+use ::num::ToPrimitive;
+
 use crate::prelude::*;
 
 pub trait InfallibleFromStr<'a> {
@@ -101,14 +107,263 @@ pub fn drop_prefix<'l>(s: &'l str, prefix: &str) -> &'l str {
     split1(s, prefix).1
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberKind {
+    Int,
+    /// Has a decimal point or an exponent, e.g. `-1.5` or `3e8`.
+    Float,
+    /// Prefixed by `0x`, e.g. `0x1f`.
+    Hex,
+}
+
+/// A number found in the input by [`tokenize_numbers`]. `start` is the byte
+/// offset of `text` (including any sign) in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NumberToken<'a> {
+    pub start: usize,
+    pub text: &'a str,
+    pub kind: NumberKind,
+}
+
+impl<'a> NumberToken<'a> {
+    fn sign_and_digits(&self) -> (bool, &'a str) {
+        match self.text.as_bytes()[0] {
+            b'-' => (true, &self.text[1..]),
+            b'+' => (false, &self.text[1..]),
+            _ => (false, self.text),
+        }
+    }
+
+    /// The exact value of the token.
+    pub fn to_big_rational(self) -> BigRational {
+        let (negative, digits) = self.sign_and_digits();
+        let value = match self.kind {
+            NumberKind::Int => BigRational::from_integer(digits.parse().unwrap()),
+            NumberKind::Hex => {
+                BigRational::from_integer(BigInt::parse_bytes(&digits.as_bytes()[2..], 16).unwrap())
+            }
+            NumberKind::Float => {
+                let (mantissa, exponent) = match digits.split_once(['e', 'E']) {
+                    Some((m, e)) => (m, parse1::<i64>(e.trim_start_matches('+'))),
+                    None => (digits, 0),
+                };
+                let (whole, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+                let n: BigInt = format!("{}{}", whole, frac).parse().unwrap();
+                let scale = exponent - frac.len() as i64;
+                let pow10 = BigInt::from(10).pow(scale.unsigned_abs() as u32);
+                if scale >= 0 {
+                    BigRational::from_integer(n * pow10)
+                } else {
+                    BigRational::new(n, pow10)
+                }
+            }
+        };
+        if negative {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn to_big_integer(self) -> BigInt {
+        let value = self.to_big_rational();
+        assert!(value.is_integer(), "{:?} is not an integer", self.text);
+        value.to_integer()
+    }
+}
+
+/// Finds every number in `input`: optionally signed integers, decimals with
+/// an optional exponent, and `0x` prefixed hex.
+pub fn tokenize_numbers(input: &str) -> impl Iterator<Item = NumberToken<'_>> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| {
+        Regex::new(r"[-+]?(?:(0[xX][0-9a-fA-F]+)|\d+(\.\d+)?([eE][-+]?\d+)?)").unwrap()
+    });
+
+    regex.captures_iter(input).map(|c| {
+        let m = c.get(0).unwrap();
+        let kind = if c.get(1).is_some() {
+            NumberKind::Hex
+        } else if c.get(2).is_some() || c.get(3).is_some() {
+            NumberKind::Float
+        } else {
+            NumberKind::Int
+        };
+        NumberToken {
+            start: m.start(),
+            text: m.as_str(),
+            kind,
+        }
+    })
+}
+
+/// Finds every run of digits in `input`, with an optional leading `-`. Unlike
+/// [`tokenize_numbers`], decimal points and exponents split the number, so
+/// `1.5` is `1` and `5`.
+pub fn tokenize_ints(input: &str) -> impl Iterator<Item = NumberToken<'_>> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = REGEX.get_or_init(|| Regex::new(r"-?\d+").unwrap());
+
+    regex.find_iter(input).map(|m| NumberToken {
+        start: m.start(),
+        text: m.as_str(),
+        kind: NumberKind::Int,
+    })
+}
+
+pub trait FromNumberToken: Sized {
+    fn from_token(token: &NumberToken) -> Self;
+}
+
+macro_rules! impl_from_number_token_int {
+    ( $($t:ty),* ) => {
+        $( impl FromNumberToken for $t {
+            fn from_token(token: &NumberToken) -> Self {
+                match token.kind {
+                    NumberKind::Int => token.text.parse().expect("Failed to parse number"),
+                    _ => token.to_big_integer().try_into().expect("Number out of range"),
+                }
+            }
+        }) *
+    }
+}
+
+impl_from_number_token_int!(i64, i128);
+
+impl FromNumberToken for BigInt {
+    fn from_token(token: &NumberToken) -> Self {
+        token.to_big_integer()
+    }
+}
+
+impl FromNumberToken for f64 {
+    fn from_token(token: &NumberToken) -> Self {
+        match token.kind {
+            NumberKind::Hex => token.to_big_integer().to_f64().unwrap(),
+            _ => token.text.parse().expect("Failed to parse number"),
+        }
+    }
+}
+
+impl FromNumberToken for BigRational {
+    fn from_token(token: &NumberToken) -> Self {
+        token.to_big_rational()
+    }
+}
+
+impl FromNumberToken for Rational64 {
+    fn from_token(token: &NumberToken) -> Self {
+        let value = token.to_big_rational();
+        Rational64::new(
+            value.numer().try_into().expect("Number out of range"),
+            value.denom().try_into().expect("Number out of range"),
+        )
+    }
+}
+
+pub fn parse_numbers<T: FromNumberToken>(input: &str) -> Vec<T> {
+    tokenize_numbers(input).map(|t| T::from_token(&t)).collect()
+}
+
 pub fn parse_ints(input: &str) -> Vec<i64> {
-    let regex = Regex::new(r"-?\d+").unwrap();
-    regex.find_iter(input).map(|x| parse1(x.as_str())).collect()
+    tokenize_ints(input).map(|t| i64::from_token(&t)).collect()
 }
 
 pub fn parse_floats(input: &str) -> Vec<f64> {
-    let regex = Regex::new(r"-?\d+").unwrap();
-    regex.find_iter(input).map(|x| parse1(x.as_str())).collect()
+    parse_numbers(input)
+}
+
+/// A block of non-blank lines, as produced by [`Sections`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    /// The `label` of a leading `label:`, if headers are enabled.
+    pub header: Option<&'a str>,
+    /// 1-based line number where the section starts.
+    pub line: usize,
+    /// The body of the section with 1-based line numbers, without the header
+    /// or line endings.
+    pub numbered_lines: Vec<(usize, &'a str)>,
+}
+
+impl<'a> Section<'a> {
+    pub fn lines(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.numbered_lines.iter().map(|(_, l)| *l)
+    }
+
+    /// The body joined with `\n`, for parsers which take the whole block.
+    pub fn text(&self) -> String {
+        self.lines().collect::<Vec<_>>().join("\n")
+    }
+
+    pub fn numbers<T: FromNumberToken>(&self) -> Vec<T> {
+        self.lines().flat_map(parse_numbers).collect()
+    }
+
+    /// Parses each line of the body with `f`, panicking with the line number
+    /// of any line it rejects.
+    pub fn parse_lines<T>(&self, mut f: impl FnMut(&'a str) -> Option<T>) -> Vec<T> {
+        self.numbered_lines
+            .iter()
+            .map(|(n, l)| f(l).unwrap_or_else(|| panic!("line {}: failed to parse {:?}", n, l)))
+            .collect()
+    }
+}
+
+/// Splits the input into blocks separated by blank lines, tolerating `\r\n`
+/// line endings and whitespace-only separators.
+#[derive(Debug, Clone)]
+pub struct Sections<'a> {
+    lines: Peekable<Enumerate<Lines<'a>>>,
+    headers: bool,
+}
+
+impl<'a> Sections<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Sections {
+            lines: input.lines().enumerate().peekable(),
+            headers: false,
+        }
+    }
+
+    /// Treats a `label:` at the start of each section as its header, e.g.
+    /// `seed-to-soil map:`. Anything following the colon on the same line is
+    /// kept as the first line of the body.
+    pub fn with_headers(mut self) -> Self {
+        self.headers = true;
+        self
+    }
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = Section<'a>;
+
+    fn next(&mut self) -> Option<Section<'a>> {
+        while self.lines.next_if(|(_, l)| l.trim().is_empty()).is_some() {}
+
+        let mut numbered_lines = vec![];
+        while let Some((idx, l)) = self.lines.next_if(|(_, l)| !l.trim().is_empty()) {
+            numbered_lines.push((idx + 1, l.trim_end_matches('\r')));
+        }
+        let line = numbered_lines.first()?.0;
+
+        let mut header = None;
+        if self.headers {
+            if let Some((label, rest)) = numbered_lines[0].1.split_once(':') {
+                header = Some(label.trim());
+                if rest.trim().is_empty() {
+                    numbered_lines.remove(0);
+                } else {
+                    numbered_lines[0].1 = rest.trim();
+                }
+            }
+        }
+
+        Some(Section {
+            header,
+            line,
+            numbered_lines,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -135,4 +390,74 @@ mod tests {
         assert_eq!(num, 1);
         assert_eq!(m["green"], 5);
     }
+
+    #[test]
+    fn test_tokenize_numbers() {
+        let tokens = tokenize_numbers("x=-12, y=+3.5e2 z=0x1F 5..10")
+            .map(|t| (t.start, t.text, t.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            vec![
+                (2, "-12", NumberKind::Int),
+                (9, "+3.5e2", NumberKind::Float),
+                (18, "0x1F", NumberKind::Hex),
+                (23, "5", NumberKind::Int),
+                (26, "10", NumberKind::Int),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_numbers() {
+        assert_eq!(parse_ints("1,-2 @ 3"), vec![1, -2, 3]);
+        assert_eq!(parse_ints("v1.2"), vec![1, 2]);
+        assert_eq!(parse_ints("#1b5e12"), vec![1, 5, 12]);
+        assert_eq!(parse_floats("1.5, -2e-1 0x10"), vec![1.5, -0.2, 16.0]);
+        assert_eq!(
+            parse_numbers::<i128>("170141183460469231731687303715884105727 1e3"),
+            vec![i128::MAX, 1000]
+        );
+        assert_eq!(
+            parse_numbers::<BigInt>("-0xff 123456789012345678901234567890"),
+            vec![
+                BigInt::from(-255),
+                "123456789012345678901234567890".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            parse_numbers::<Rational64>("0.25 -1.5e-1"),
+            vec![Rational64::new(1, 4), Rational64::new(-3, 20)]
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let input =
+            "seeds: 79 14\r\n\r\nseed-to-soil map:\r\n50 98 2\r\n52 50 48\r\n  \r\n\r\nlast\r\n";
+        let sections = Sections::new(input).with_headers().collect::<Vec<_>>();
+        assert_eq!(sections.len(), 3);
+
+        assert_eq!(sections[0].header, Some("seeds"));
+        assert_eq!(sections[0].numbers::<i64>(), vec![79, 14]);
+
+        assert_eq!(sections[1].header, Some("seed-to-soil map"));
+        assert_eq!(sections[1].line, 3);
+        assert_eq!(
+            sections[1].numbered_lines,
+            vec![(4, "50 98 2"), (5, "52 50 48")]
+        );
+        assert_eq!(sections[1].text(), "50 98 2\n52 50 48");
+
+        assert_eq!(sections[2].header, None);
+        assert_eq!(sections[2].line, 8);
+        assert_eq!(Sections::new("a:b").next().unwrap().text(), "a:b");
+    }
+
+    #[test]
+    #[should_panic(expected = "line 3")]
+    fn test_section_parse_error() {
+        let section = Sections::new("1\n2\nx").next().unwrap();
+        section.parse_lines(|l| l.parse::<i64>().ok());
+    }
 }
@@ -0,0 +1,153 @@
+use crate::prelude::*;
+
+/// An axis-aligned box, which falls along -z.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Block {
+    pub x: RangeInclusive<i64>,
+    pub y: RangeInclusive<i64>,
+    pub z: RangeInclusive<i64>,
+}
+
+impl Block {
+    /// The (x, y) cells that the block covers.
+    pub fn footprint(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.x
+            .clone()
+            .flat_map(move |x| self.y.clone().map(move |y| (x, y)))
+    }
+}
+
+/// The result of letting a set of blocks fall until they come to rest. All of
+/// the fields are indexed the same way as the blocks passed to [`settle`].
+#[derive(Debug, Clone)]
+pub struct SettledBlocks {
+    pub blocks: Vec<Block>,
+    /// Blocks resting directly on top of each block.
+    pub supports: Vec<HashSet<usize>>,
+    /// Blocks directly underneath each block.
+    pub supported_by: Vec<HashSet<usize>>,
+    /// Dominator tree of the support graph, rooted at the ground.
+    pub dominators: DominatorTree<usize>,
+}
+
+/// Makes the blocks fall down onto the ground (at z = 0) and onto each other.
+pub fn settle(mut blocks: Vec<Block>) -> SettledBlocks {
+    let mut order = (0..blocks.len()).collect::<Vec<_>>();
+    order.sort_by_key(|idx| *blocks[*idx].z.start());
+
+    let mut supports = vec![HashSet::default(); blocks.len()];
+    let mut supported_by = vec![HashSet::default(); blocks.len()];
+
+    // Highest z and which block it belongs to, for every (x, y) seen so far
+    let mut height_map: HashMap<(i64, i64), (i64, usize)> = HashMap::default();
+
+    for idx in order {
+        let b = &blocks[idx];
+        let top = b
+            .footprint()
+            .filter_map(|p| height_map.get(&p))
+            .map(|(z, _)| *z)
+            .max()
+            .unwrap_or(0);
+
+        for p in b.footprint() {
+            match height_map.get(&p) {
+                Some((z, below)) if *z == top => {
+                    supports[*below].insert(idx);
+                    supported_by[idx].insert(*below);
+                }
+                _ => (),
+            }
+        }
+
+        let delta = b.z.start() - (top + 1);
+        let z = b.z.start() - delta..=b.z.end() - delta;
+        for p in b.footprint() {
+            height_map.insert(p, (*z.end(), idx));
+        }
+        blocks[idx].z = z;
+    }
+
+    // A block falls when everything supporting it falls, so it falls when a
+    // block that dominates it is removed.
+    let dominators = support_dominator_tree(
+        supported_by
+            .iter()
+            .enumerate()
+            .map(|(idx, below)| (idx, below.iter().copied())),
+    );
+
+    SettledBlocks {
+        blocks,
+        supports,
+        supported_by,
+        dominators,
+    }
+}
+
+impl SettledBlocks {
+    /// Blocks which can be removed without any other block falling.
+    pub fn removable(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.blocks.len()).filter(|idx| {
+            self.supports[*idx]
+                .iter()
+                .all(|above| self.supported_by[*above].len() >= 2)
+        })
+    }
+
+    /// Every other block which falls if block `idx` is removed.
+    pub fn would_fall(&self, idx: usize) -> Vec<usize> {
+        self.dominators.dominated(&idx)
+    }
+
+    /// Number of other blocks which fall if block `idx` is removed.
+    pub fn fall_count(&self, idx: usize) -> usize {
+        self.dominators.subtree_size[&idx] - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settle() {
+        // Two blocks side by side, bridged by a third; a fourth sits on top.
+        let blocks = vec![
+            Block {
+                x: 0..=0,
+                y: 0..=0,
+                z: 5..=6,
+            },
+            Block {
+                x: 2..=2,
+                y: 0..=0,
+                z: 3..=3,
+            },
+            Block {
+                x: 0..=2,
+                y: 0..=0,
+                z: 10..=10,
+            },
+            Block {
+                x: 1..=1,
+                y: 0..=0,
+                z: 20..=20,
+            },
+        ];
+        let settled = settle(blocks);
+        assert_eq!(
+            settled
+                .blocks
+                .iter()
+                .map(|b| b.z.clone())
+                .collect::<Vec<_>>(),
+            vec![1..=2, 1..=1, 3..=3, 4..=4]
+        );
+        assert_eq!(settled.supported_by[2], [0].into_iter().collect());
+        assert_eq!(settled.removable().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(settled.would_fall(0).len(), 2);
+        assert_eq!(settled.fall_count(0), 2);
+        assert!(settled.would_fall(2).contains(&3));
+    }
+}
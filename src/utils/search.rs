@@ -0,0 +1,370 @@
+use std::sync::atomic::{AtomicI64, Ordering as AtomicOrdering};
+
+use crate::prelude::*;
+
+/// A fixed-size set of `usize`s, stored as a bitmask.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(n: usize) -> Self {
+        BitSet {
+            words: vec![0; n.div_ceil(64)],
+        }
+    }
+
+    pub fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(idx, w)| {
+            (0..64)
+                .filter(move |b| w & (1 << b) != 0)
+                .map(move |b| idx * 64 + b)
+        })
+    }
+}
+
+enum Outcome {
+    /// The longest path from the node to the end, if there is one.
+    Exact(Option<i64>),
+    /// Part of the search was skipped because it couldn't beat the best path
+    /// found so far.
+    Pruned,
+}
+
+struct LongestPathSearch<'a> {
+    graph: &'a [Vec<(usize, i64)>],
+    end: usize,
+    best: &'a AtomicI64,
+    memo: HashMap<(usize, BitSet), Option<i64>>,
+}
+
+impl LongestPathSearch<'_> {
+    /// All nodes which can be reached from `node` without passing through
+    /// `visited`, including `node` itself.
+    fn reachable(&self, node: usize, visited: &BitSet) -> BitSet {
+        let mut reachable = BitSet::new(self.graph.len());
+        reachable.insert(node);
+        let mut stk = vec![node];
+        while let Some(n) = stk.pop() {
+            for (next, _) in &self.graph[n] {
+                if !visited.contains(*next) && !reachable.contains(*next) {
+                    reachable.insert(*next);
+                    stk.push(*next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Every node on the path contributes at most its heaviest edge.
+    fn upper_bound(&self, reachable: &BitSet) -> i64 {
+        reachable
+            .iter()
+            .filter(|n| *n != self.end)
+            .map(|n| {
+                self.graph[n]
+                    .iter()
+                    .filter(|(next, _)| reachable.contains(*next))
+                    .map(|(_, w)| *w)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .sum()
+    }
+
+    fn visit(&mut self, node: usize, visited: &mut BitSet, steps: i64) -> Outcome {
+        if node == self.end {
+            self.best.fetch_max(steps, AtomicOrdering::Relaxed);
+            return Outcome::Exact(Some(0));
+        }
+
+        let reachable = self.reachable(node, visited);
+        if !reachable.contains(self.end) {
+            return Outcome::Exact(None);
+        }
+
+        // The rest of the path only depends on which nodes are still
+        // reachable, not how we got here.
+        let key = (node, reachable);
+        if let Some(r) = self.memo.get(&key) {
+            if let Some(v) = r {
+                self.best.fetch_max(steps + v, AtomicOrdering::Relaxed);
+            }
+            return Outcome::Exact(*r);
+        }
+        if steps + self.upper_bound(&key.1) <= self.best.load(AtomicOrdering::Relaxed) {
+            return Outcome::Pruned;
+        }
+
+        visited.insert(node);
+        let mut longest = None;
+        let mut exact = true;
+        for (next, w) in &self.graph[node] {
+            if visited.contains(*next) {
+                continue;
+            }
+            match self.visit(*next, visited, steps + w) {
+                Outcome::Exact(Some(v)) => longest = longest.max(Some(v + w)),
+                Outcome::Exact(None) => (),
+                Outcome::Pruned => exact = false,
+            }
+        }
+        visited.remove(node);
+
+        if exact {
+            self.memo.insert(key, longest);
+            Outcome::Exact(longest)
+        } else {
+            Outcome::Pruned
+        }
+    }
+}
+
+/// Finds the length of the longest path from `start` to `end` which doesn't
+/// visit any node twice, where `graph[n]` is the list of `(next, weight)`
+/// edges out of node `n`. Weights must be non-negative.
+///
+/// If `parallel` is set, each edge out of `start` is searched on its own
+/// thread.
+pub fn longest_simple_path(
+    graph: &[Vec<(usize, i64)>],
+    start: usize,
+    end: usize,
+    parallel: bool,
+) -> Option<i64> {
+    let best = AtomicI64::new(i64::MIN);
+    let search = || LongestPathSearch {
+        graph,
+        end,
+        best: &best,
+        memo: HashMap::default(),
+    };
+
+    if parallel && start != end {
+        std::thread::scope(|scope| {
+            for (next, w) in &graph[start] {
+                let mut s = search();
+                scope.spawn(move || {
+                    let mut visited = BitSet::new(graph.len());
+                    visited.insert(start);
+                    s.visit(*next, &mut visited, *w);
+                });
+            }
+        });
+    } else {
+        search().visit(start, &mut BitSet::new(graph.len()), 0);
+    }
+
+    match best.into_inner() {
+        i64::MIN => None,
+        v => Some(v),
+    }
+}
+
+/// Describes how a crucible-style mover is allowed to move through a grid.
+/// Every step moves one cell in the current direction; a "run" is the
+/// number of consecutive steps in the same direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovementRules {
+    /// Minimum run before turning, reversing or stopping at the destination.
+    pub min_straight: i64,
+    /// Maximum run before being forced to change direction.
+    pub max_straight: i64,
+    pub allow_turns: bool,
+    pub allow_reversal: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathResult {
+    pub cost: i64,
+    /// Every cell visited, including the start and the destination.
+    pub path: Vec<Point>,
+}
+
+/// Dijkstra from `start` to `end`, following `rules`. Entering a cell costs
+/// `cost(ch)`, or it can't be entered if that returns `None`.
+pub fn constrained_shortest_path(
+    g: &IGrid2D,
+    start: Point,
+    end: Point,
+    rules: &MovementRules,
+    cost: impl Fn(char) -> Option<i64>,
+) -> Option<PathResult> {
+    // (pos, direction, run length); the start has no direction yet
+    type State = (Point, Point, i64);
+
+    let start_state: State = (start, (0, 0), 0);
+    let mut dist = HashMap::default();
+    let mut prev: HashMap<State, State> = HashMap::default();
+    let mut q = BinaryHeap::new();
+    dist.insert(start_state, 0);
+    q.push(Reverse((0, start_state)));
+
+    while let Some(Reverse((c, state @ (pos, dir, run)))) = q.pop() {
+        if c > dist[&state] {
+            continue;
+        }
+        if pos == end && run >= rules.min_straight {
+            let mut path = vec![pos];
+            let mut s = state;
+            while let Some(p) = prev.get(&s) {
+                path.push(p.0);
+                s = *p;
+            }
+            path.reverse();
+            return Some(PathResult { cost: c, path });
+        }
+
+        let can_change = dir == (0, 0) || run >= rules.min_straight;
+        for d in FOUR_WAY {
+            let allowed = if dir == (0, 0) {
+                true
+            } else if d == dir {
+                run < rules.max_straight
+            } else if d == point_neg(dir) {
+                can_change && rules.allow_reversal
+            } else {
+                can_change && rules.allow_turns
+            };
+            if !allowed {
+                continue;
+            }
+
+            let next_pos = point_add(pos, d);
+            if !g.contains_key(&next_pos) {
+                continue;
+            }
+            let Some(step_cost) = cost(g[next_pos]) else {
+                continue;
+            };
+
+            let next = (next_pos, d, if d == dir { run + 1 } else { 1 });
+            let next_cost = c + step_cost;
+            if dist.get(&next).map(|d| next_cost < *d).unwrap_or(true) {
+                dist.insert(next, next_cost);
+                prev.insert(next, state);
+                q.push(Reverse((next_cost, next)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset() {
+        let mut b = BitSet::new(130);
+        b.insert(3);
+        b.insert(129);
+        b.insert(64);
+        assert!(b.contains(129));
+        assert!(!b.contains(128));
+        assert_eq!(b.iter().collect::<Vec<_>>(), vec![3, 64, 129]);
+        b.remove(64);
+        assert_eq!(b.len(), 2);
+    }
+
+    #[test]
+    fn test_longest_simple_path() {
+        // 0 - 1 - 3, with a detour 1 - 2 - 3 and a dead end at 4
+        let mut g = vec![vec![]; 5];
+        for (a, b, w) in [(0, 1, 1), (1, 3, 1), (1, 2, 5), (2, 3, 5), (2, 4, 100)] {
+            g[a].push((b, w));
+            g[b].push((a, w));
+        }
+        for parallel in [false, true] {
+            assert_eq!(longest_simple_path(&g, 0, 3, parallel), Some(11));
+            assert_eq!(longest_simple_path(&g, 4, 0, parallel), Some(107));
+            assert_eq!(longest_simple_path(&g, 0, 0, parallel), Some(0));
+        }
+
+        g[3].clear();
+        g[1].retain(|(n, _)| *n != 3);
+        g[2].retain(|(n, _)| *n != 3);
+        assert_eq!(longest_simple_path(&g, 0, 3, false), None);
+    }
+
+    #[test]
+    fn test_longest_simple_path_large() {
+        // A ladder with more than 64 nodes
+        let n = 100;
+        let mut g = vec![vec![]; 2 * n];
+        for i in 0..n {
+            let (a, b) = (2 * i, 2 * i + 1);
+            g[a].push((b, 1));
+            g[b].push((a, 1));
+            if i + 1 < n {
+                g[a].push((a + 2, 1));
+                g[a + 2].push((a, 1));
+                g[b].push((b + 2, 1));
+                g[b + 2].push((b, 1));
+            }
+        }
+        // Snaking through every rung visits every node.
+        assert_eq!(
+            longest_simple_path(&g, 0, 2 * n - 2, true),
+            Some(2 * n as i64 - 1)
+        );
+    }
+
+    #[test]
+    fn test_constrained_shortest_path() {
+        let g = parse_char_grid("19111\n11191\n99991");
+        let cost = |c: char| c.to_digit(10).map(|d| d as i64);
+        let rules = MovementRules {
+            min_straight: 1,
+            max_straight: 3,
+            allow_turns: true,
+            allow_reversal: false,
+        };
+
+        let res = constrained_shortest_path(&g, (0, 0), (2, 4), &rules, cost).unwrap();
+        assert_eq!(res.cost, 8);
+        assert_eq!(res.path.first(), Some(&(0, 0)));
+        assert_eq!(res.path.last(), Some(&(2, 4)));
+        assert_eq!(
+            res.path
+                .iter()
+                .skip(1)
+                .map(|p| cost(g[p]).unwrap())
+                .sum::<i64>(),
+            res.cost
+        );
+
+        // Going straight along the top row is too long a run
+        let g = parse_char_grid("11111\n11111");
+        for (max_straight, expected) in [(4, 4), (2, 6)] {
+            let rules = MovementRules {
+                max_straight,
+                ..rules
+            };
+            let res = constrained_shortest_path(&g, (0, 0), (0, 4), &rules, cost).unwrap();
+            assert_eq!(res.cost, expected);
+        }
+    }
+}
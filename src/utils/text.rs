@@ -0,0 +1,164 @@
+use crate::prelude::*;
+
+/// An occurrence of a vocabulary word in a haystack, with byte offsets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Match<V> {
+    pub start: usize,
+    pub end: usize,
+    pub value: V,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    next: HashMap<u8, usize>,
+    fail: usize,
+    /// Words ending at this node, including those reached by fail links.
+    outputs: Vec<usize>,
+}
+
+/// Finds every occurrence of a set of words in a single pass over the input
+/// using an Aho-Corasick automaton. Matches may overlap, so e.g. "eightwo"
+/// contains both "eight" and "two".
+#[derive(Debug, Clone)]
+pub struct Scanner<V> {
+    nodes: Vec<Node>,
+    words: Vec<(usize, V)>,
+}
+
+impl<V: Clone> Scanner<V> {
+    pub fn new<S: AsRef<str>>(vocabulary: impl IntoIterator<Item = (S, V)>) -> Self {
+        let mut nodes = vec![Node::default()];
+        let mut words = vec![];
+
+        for (word, value) in vocabulary {
+            let word = word.as_ref();
+            assert!(!word.is_empty(), "can't scan for the empty string");
+
+            let mut n = 0;
+            for b in word.bytes() {
+                n = match nodes[n].next.get(&b) {
+                    Some(child) => *child,
+                    None => {
+                        nodes.push(Node::default());
+                        let child = nodes.len() - 1;
+                        nodes[n].next.insert(b, child);
+                        child
+                    }
+                };
+            }
+            nodes[n].outputs.push(words.len());
+            words.push((word.len(), value));
+        }
+
+        // Fill in fail links breadth first, so a node's fail target is always
+        // complete before the node itself.
+        let mut q = nodes[0].next.values().cloned().collect::<VecDeque<_>>();
+        while let Some(n) = q.pop_front() {
+            let edges = nodes[n].next.clone();
+            for (b, child) in edges {
+                let mut f = nodes[n].fail;
+                let fail = loop {
+                    if let Some(target) = nodes[f].next.get(&b) {
+                        break *target;
+                    } else if f == 0 {
+                        break 0;
+                    }
+                    f = nodes[f].fail;
+                };
+                nodes[child].fail = fail;
+                let inherited = nodes[fail].outputs.clone();
+                nodes[child].outputs.extend(inherited);
+                q.push_back(child);
+            }
+        }
+
+        Scanner { nodes, words }
+    }
+
+    fn step(&self, mut n: usize, b: u8) -> usize {
+        loop {
+            if let Some(next) = self.nodes[n].next.get(&b) {
+                return *next;
+            } else if n == 0 {
+                return 0;
+            }
+            n = self.nodes[n].fail;
+        }
+    }
+
+    /// Every match in `haystack`, ordered by end position.
+    pub fn find_all<'a>(&'a self, haystack: &'a str) -> impl Iterator<Item = Match<V>> + 'a {
+        haystack
+            .bytes()
+            .enumerate()
+            .scan(0, move |n, (i, b)| {
+                *n = self.step(*n, b);
+                Some((i + 1, *n))
+            })
+            .flat_map(move |(end, n)| {
+                self.nodes[n].outputs.iter().map(move |w| {
+                    let (len, value) = &self.words[*w];
+                    Match {
+                        start: end - len,
+                        end,
+                        value: value.clone(),
+                    }
+                })
+            })
+    }
+
+    /// The matches starting furthest left and furthest right, preferring the
+    /// longer match when several start at the same place.
+    pub fn first_and_last(&self, haystack: &str) -> Option<(Match<V>, Match<V>)> {
+        let mut matches = self.find_all(haystack);
+        let m = matches.next()?;
+        let (mut first, mut last) = (m.clone(), m);
+        for m in matches {
+            if (m.start, Reverse(m.end)) < (first.start, Reverse(first.end)) {
+                first = m.clone();
+            }
+            if (m.start, m.end) > (last.start, last.end) {
+                last = m;
+            }
+        }
+        Some((first, last))
+    }
+
+    pub fn first(&self, haystack: &str) -> Option<Match<V>> {
+        self.first_and_last(haystack).map(|(first, _)| first)
+    }
+
+    pub fn last(&self, haystack: &str) -> Option<Match<V>> {
+        self.first_and_last(haystack).map(|(_, last)| last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scanner() {
+        let scanner = Scanner::new([("he", 1), ("she", 2), ("his", 3), ("hers", 4)]);
+        let found = scanner
+            .find_all("ushers")
+            .map(|m| (m.start, m.value))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![(1, 2), (2, 1), (2, 4)]);
+
+        let (first, last) = scanner.first_and_last("ushers").unwrap();
+        assert_eq!((first.start, first.end, first.value), (1, 4, 2));
+        assert_eq!((last.start, last.end, last.value), (2, 6, 4));
+        assert_eq!(scanner.first("xyz"), None);
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let scanner = Scanner::new([("eight", 8), ("two", 2), ("one", 1)]);
+        let found = scanner
+            .find_all("eightwone")
+            .map(|m| m.value)
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![8, 2, 1]);
+    }
+}
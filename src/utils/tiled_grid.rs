@@ -0,0 +1,184 @@
+use crate::prelude::*;
+
+/// An `IGrid2D` which repeats infinitely in every direction.
+#[derive(Debug, Clone)]
+pub struct TiledGrid {
+    grid: IGrid2D,
+    i_bounds: Range<i64>,
+    j_bounds: Range<i64>,
+}
+
+impl TiledGrid {
+    pub fn new(grid: IGrid2D) -> Self {
+        let (i_bounds, j_bounds) = get_grid_bounds(&grid);
+        TiledGrid {
+            grid,
+            i_bounds,
+            j_bounds,
+        }
+    }
+
+    pub fn grid(&self) -> &IGrid2D {
+        &self.grid
+    }
+
+    /// Returns the (height, width) of a single tile.
+    pub fn tile_size(&self) -> (i64, i64) {
+        (
+            self.i_bounds.end - self.i_bounds.start,
+            self.j_bounds.end - self.j_bounds.start,
+        )
+    }
+
+    /// Maps any point onto the corresponding point in the original grid.
+    pub fn wrap(&self, (i, j): Point) -> Point {
+        let (h, w) = self.tile_size();
+        (
+            self.i_bounds.start + (i - self.i_bounds.start).rem_euclid(h),
+            self.j_bounds.start + (j - self.j_bounds.start).rem_euclid(w),
+        )
+    }
+
+    /// Returns the tile that the point is in; the original grid is tile
+    /// (0, 0).
+    pub fn tile_of(&self, (i, j): Point) -> Point {
+        let (h, w) = self.tile_size();
+        (
+            (i - self.i_bounds.start).div_euclid(h),
+            (j - self.j_bounds.start).div_euclid(w),
+        )
+    }
+
+    /// BFS outwards from `start` for up to `max_steps` steps, only moving
+    /// `FOUR_WAY` onto cells where `passable` is true.
+    pub fn bfs(
+        &self,
+        start: Point,
+        max_steps: i64,
+        passable: impl Fn(char) -> bool,
+    ) -> TiledBfs<'_> {
+        let mut distances = HashMap::default();
+        distances.insert(start, 0);
+
+        let mut q = VecDeque::new();
+        q.push_back((start, 0));
+
+        while let Some((pos, steps)) = q.pop_front() {
+            if steps == max_steps {
+                continue;
+            }
+            for next in adjacents(pos, FOUR_WAY) {
+                if !distances.contains_key(&next) && passable(self[next]) {
+                    distances.insert(next, steps + 1);
+                    q.push_back((next, steps + 1));
+                }
+            }
+        }
+
+        TiledBfs {
+            distances,
+            max_steps,
+            tiling: self,
+        }
+    }
+}
+
+impl Index<Point> for TiledGrid {
+    type Output = char;
+
+    fn index(&self, p: Point) -> &char {
+        &self.grid[self.wrap(p)]
+    }
+}
+
+/// The shortest distances found by [`TiledGrid::bfs`].
+#[derive(Debug, Clone)]
+pub struct TiledBfs<'a> {
+    pub distances: HashMap<Point, i64>,
+    /// How far the BFS explored; nothing further away is in `distances`.
+    pub max_steps: i64,
+    tiling: &'a TiledGrid,
+}
+
+impl TiledBfs<'_> {
+    /// Points which can be at the end of a walk of exactly `steps` steps, i.e.
+    /// those which are at most `steps` away with the same parity. `steps` can't
+    /// be more than the `max_steps` the BFS was run with.
+    pub fn frontier(&self, steps: i64) -> impl Iterator<Item = Point> + '_ {
+        assert!(
+            steps <= self.max_steps,
+            "BFS only explored {} steps, not {}",
+            self.max_steps,
+            steps
+        );
+        self.distances
+            .iter()
+            .filter(move |(_, d)| **d <= steps && (steps - **d) % 2 == 0)
+            .map(|(p, _)| *p)
+    }
+
+    /// Number of points which can be at the end of a walk of exactly `steps`
+    /// steps.
+    pub fn count_frontier(&self, steps: i64) -> i64 {
+        self.frontier(steps).count() as i64
+    }
+
+    /// Number of points which can be at the end of a walk of exactly `steps`
+    /// steps, grouped by tile.
+    pub fn count_frontier_per_tile(&self, steps: i64) -> DefaultHashMap<Point, i64> {
+        let mut counts = DefaultHashMap::default();
+        for p in self.frontier(steps) {
+            counts[self.tiling.tile_of(p)] += 1;
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = r#"...........
+.....###.#.
+.###.##..#.
+..#.#...#..
+....#.#....
+.##..S####.
+.##..#...#.
+.......##..
+.##.#.####.
+.##..##.##.
+..........."#;
+
+    #[test]
+    fn test_wrap() {
+        let g = TiledGrid::new(parse_char_grid(EXAMPLE));
+        assert_eq!(g.tile_size(), (11, 11));
+        assert_eq!(g.wrap((-1, 11)), (10, 0));
+        assert_eq!(g.tile_of((-1, 11)), (-1, 1));
+        assert_eq!(g.tile_of((10, 10)), (0, 0));
+        assert_eq!(g[(5, 16)], 'S');
+    }
+
+    #[test]
+    fn test_bfs() {
+        let g = TiledGrid::new(parse_char_grid(EXAMPLE));
+        let bfs = g.bfs((5, 5), 100, |c| c == '.' || c == 'S');
+        assert_eq!(bfs.count_frontier(6), 16);
+        assert_eq!(bfs.count_frontier(10), 50);
+        assert_eq!(bfs.count_frontier(50), 1594);
+        assert_eq!(bfs.count_frontier(100), 6536);
+
+        let per_tile = bfs.count_frontier_per_tile(10);
+        assert_eq!(per_tile.values().sum::<i64>(), 50);
+        assert!(per_tile[(0, -1)] > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "BFS only explored 100 steps")]
+    fn test_bfs_horizon() {
+        let g = TiledGrid::new(parse_char_grid(EXAMPLE));
+        g.bfs((5, 5), 100, |c| c == '.' || c == 'S')
+            .count_frontier(101);
+    }
+}